@@ -1,483 +1,1391 @@
 use ratatui::layout::Rect;
-use ratatui::widgets::ListItem;
-use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{BufWriter, Read, Write};
-
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
-pub struct Information {
-    pub character_name: String,
-    pub class: String,
-    pub level: u8,
-    pub background: String,
-    pub player_name: String,
-    pub race: String,
-    pub alignment: String,
-    pub experience: String,
-}
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
 
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
-// Modifiers will be calculated based on rules of the game
-pub struct Statistics {
-    pub strength: u8,
-    pub dexterity: u8,
-    pub constitution: u8,
-    pub intelligence: u8,
-    pub wisdom: u8,
-    pub charisma: u8,
-    pub inspiration: bool,
-    pub proficiency_bonus: u8,
-    pub passive_wisdom_perception: u8,
-}
+use char_sheet::dice::{self, Advantage, DieRoller, Rng, Roll};
+use char_sheet::history::{self, HistoryEntry, SessionStats};
+use char_sheet::inventory::{Container, Item};
+use char_sheet::rest::{Rest, RestKind};
+use char_sheet::rules;
+use char_sheet::sheet::{
+    Ability, CharSheet, DeathSaveOutcome, HpChange, HpChangeKind, Information, LifeState,
+    RollTarget, Skill,
+};
+use char_sheet::spells::{self, Preparation, SlotSource, Spellcasting};
+use char_sheet::storage::{self, Backup, LoadError};
+use char_sheet::wallet;
 
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
-pub struct SavingThrows {
-    pub strength_proficent: bool,
-    pub dexterity_proficent: bool,
-    pub constitution_proficent: bool,
-    pub intelligence_proficent: bool,
-    pub wisdom_proficent: bool,
-    pub charisma_proficent: bool,
-}
+use crate::cli::Options;
+use crate::input::TextInput;
+use crate::theme::Theme;
 
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
-pub struct Skills {
-    pub acrobatics: String,
-    pub animal_handling: String,
-    pub arcana: String,
-    pub athletics: String,
-    pub deception: String,
-    pub history: String,
-    pub insight: String,
-    pub intimidation: String,
-    pub investigation: String,
-    pub medicine: String,
-    pub nature: String,
-    pub perception: String,
-    pub performance: String,
-    pub persuasion: String,
-    pub religion: String,
-    pub slight_of_hand: String,
-    pub stealth: String,
-    pub survival: String,
-    pub acrobatics_skill: String,
-    pub animal_handling_skill: String,
-    pub arcana_skill: String,
-    pub athletics_skill: String,
-    pub deception_skill: String,
-    pub history_skill: String,
-    pub insight_skill: String,
-    pub intimidation_skill: String,
-    pub investigation_skill: String,
-    pub medicine_skill: String,
-    pub nature_skill: String,
-    pub perception_skill: String,
-    pub performance_skill: String,
-    pub persuasion_skill: String,
-    pub religion_skill: String,
-    pub slight_of_hand_skill: String,
-    pub stealth_skill: String,
-    pub survival_skill: String,
-}
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
-pub struct ProficienciesAndLanguage {
-    pub languages_known: String,
-    pub armor_proficiency: String,
-    pub weapon_proficiency: String,
-    pub tools_proficiency: String,
-}
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
-pub struct Health {
-    pub armor_class: u8,
-    pub initiative: String,
-    pub speed: u8,
-    pub current_hp: u8,
-    pub maximum_hp: u8,
-    pub temporary_hp: u8,
-    pub hit_dice_type: String,
-    pub total_hit_dice: u8,
-    pub current_hit_dice: u8,
-    pub unconcicious: bool,
-    pub death_save_saves: String,
-    pub death_save_failes: String,
+#[derive(Default)]
+pub struct ViewState {
+    pub health: HealthView,
+    pub info: InfoView,
+    pub stats: StatsView,
+    // The roll log panel, for scrolling with the mouse wheel.
+    pub roll_log: Rect,
+    // One row per resource in the resources panel.
+    pub resources: Vec<Rect>,
+    // TODO: Move other View's into this structure
+    // skills_view, etc
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
-pub struct CharSheet {
-    pub information: Information,
-    pub statistics: Statistics,
-    pub saving_throws: SavingThrows,
-    pub skills: Skills,
-    pub proficiencies_and_language: ProficienciesAndLanguage,
-    pub health: Health,
+#[derive(Default)]
+pub enum Hover {
+    Minus,
+    Plus,
+    #[default]
+    None,
 }
 
-#[derive(Clone, Copy)]
-pub struct StatView {
-    pub name: &'static str,
-    pub value: u8,
-    pub modifier: i8,
+#[derive(Default)]
+pub struct HealthView {
+    pub minus_rect: Rect,
+    pub plus_rect: Rect,
+    pub hover: Hover,
+    // Death save buttons, only drawn while the character is dying.
+    pub roll_rect: Rect,
+    pub success_rect: Rect,
+    pub failure_rect: Rect,
 }
 
-fn ability_mod(stat: u8) -> i8 {
-    (stat as i8 - 10) / 2
+/// Where each Character Information field was drawn, for mouse selection.
+#[derive(Default)]
+pub struct InfoView {
+    pub field_rects: Vec<(CurrEditInformation, Rect)>,
 }
 
-impl Information {
-    pub fn information_to_list_item(&self) -> Vec<ListItem<'static>> {
-        vec![
-            ListItem::new(format!("Char Name: {}", self.character_name)),
-            ListItem::new(format!("Class: {}", self.class)),
-            ListItem::new(format!("Level: {}", self.level)),
-            ListItem::new(format!("Background: {}", self.background)),
-            ListItem::new(format!("Player Name: {}", self.player_name)),
-            ListItem::new(format!("Race: {}", self.race)),
-            ListItem::new(format!("Alignment: {}", self.alignment)),
-            ListItem::new(format!("Experience: {}", self.experience)),
-        ]
-    }
+/// Where each ability box, saving throw and skill was drawn, for mouse
+/// selection.
+#[derive(Default)]
+pub struct StatsView {
+    pub ability_rects: Vec<(Ability, Rect)>,
+    pub proficiency_rects: Vec<(ProficiencyTarget, Rect)>,
 }
 
-impl Statistics {
-    pub fn ability_scores(&self) -> [StatView; 6] {
-        [
-            StatView {
-                name: "STR",
-                value: self.strength,
-                modifier: ability_mod(self.strength),
-            },
-            StatView {
-                name: "DEX",
-                value: self.dexterity,
-                modifier: ability_mod(self.dexterity),
-            },
-            StatView {
-                name: "CON",
-                value: self.constitution,
-                modifier: ability_mod(self.constitution),
-            },
-            StatView {
-                name: "INT",
-                value: self.intelligence,
-                modifier: ability_mod(self.intelligence),
-            },
-            StatView {
-                name: "WIS",
-                value: self.wisdom,
-                modifier: ability_mod(self.wisdom),
-            },
-            StatView {
-                name: "CHA",
-                value: self.charisma,
-                modifier: ability_mod(self.charisma),
-            },
-        ]
-    }
+pub enum CurrentScreen {
+    Main,
+    Editing,
+    EditingAbilities,
+    EditingProficiencies,
+    Rolling,
+    Resting,
+    Exiting,
+    LoadFailed,
+    Backups,
+    RollStats,
+    Spells,
+    Resources,
+    Inventory,
+    Wallet,
 }
 
-#[derive(Clone, Copy)]
-pub struct SavingThrowView {
-    pub name: &'static str,
-    pub value: i8,
-    pub proficient: bool,
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CurrEditInformation {
+    CharacterName,
+    Class,
+    Level,
+    Background,
+    PlayerName,
+    Race,
+    Alignment,
+    Experience,
 }
 
-impl SavingThrows {
-    pub fn saving_throw_views(&self, stats: &Statistics) -> [SavingThrowView; 6] {
-        [
-            Self::saving_throw(
-                "STR",
-                stats.strength,
-                self.strength_proficent,
-                stats.proficiency_bonus,
-            ),
-            Self::saving_throw(
-                "DEX",
-                stats.dexterity,
-                self.dexterity_proficent,
-                stats.proficiency_bonus,
-            ),
-            Self::saving_throw(
-                "CON",
-                stats.constitution,
-                self.constitution_proficent,
-                stats.proficiency_bonus,
-            ),
-            Self::saving_throw(
-                "INT",
-                stats.intelligence,
-                self.intelligence_proficent,
-                stats.proficiency_bonus,
-            ),
-            Self::saving_throw(
-                "WIS",
-                stats.wisdom,
-                self.wisdom_proficent,
-                stats.proficiency_bonus,
-            ),
-            Self::saving_throw(
-                "CHA",
-                stats.charisma,
-                self.charisma_proficent,
-                stats.proficiency_bonus,
-            ),
-        ]
+impl CurrEditInformation {
+    // In the order the fields are laid out in the Character Information panel.
+    pub const ALL: [CurrEditInformation; 8] = [
+        CurrEditInformation::CharacterName,
+        CurrEditInformation::Class,
+        CurrEditInformation::Level,
+        CurrEditInformation::Background,
+        CurrEditInformation::PlayerName,
+        CurrEditInformation::Race,
+        CurrEditInformation::Alignment,
+        CurrEditInformation::Experience,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CurrEditInformation::CharacterName => "Char Name",
+            CurrEditInformation::Class => "Class",
+            CurrEditInformation::Level => "Level",
+            CurrEditInformation::Background => "Background",
+            CurrEditInformation::PlayerName => "Player Name",
+            CurrEditInformation::Race => "Race",
+            CurrEditInformation::Alignment => "Alignment",
+            CurrEditInformation::Experience => "Experience",
+        }
     }
 
-    fn saving_throw(
-        name: &'static str,
-        score: u8,
-        proficient: bool,
-        prof_bonus: u8,
-    ) -> SavingThrowView {
-        let mut value = ability_mod(score);
+    pub fn value(self, info: &Information) -> String {
+        match self {
+            CurrEditInformation::CharacterName => info.character_name.clone(),
+            CurrEditInformation::Class => info.class.clone(),
+            CurrEditInformation::Level => info.level.to_string(),
+            CurrEditInformation::Background => info.background.clone(),
+            CurrEditInformation::PlayerName => info.player_name.clone(),
+            CurrEditInformation::Race => info.race.clone(),
+            CurrEditInformation::Alignment => info.alignment.clone(),
+            CurrEditInformation::Experience => info.experience.clone(),
+        }
+    }
 
-        if proficient {
-            value += prof_bonus as i8;
+    /// Checks a typed value, returning what should be stored for it.
+    pub fn validate(self, input: &str) -> Result<String, String> {
+        let input = input.trim();
+        match self {
+            CurrEditInformation::CharacterName if input.is_empty() => {
+                Err("The character needs a name".into())
+            }
+            CurrEditInformation::Level => match input.parse::<u8>() {
+                Ok(level) if (1..=20).contains(&level) => Ok(level.to_string()),
+                _ => Err("Level must be a number from 1 to 20".into()),
+            },
+            // Tables that level up by milestone don't count XP at all.
+            CurrEditInformation::Experience if input.eq_ignore_ascii_case("milestone") => {
+                Ok("Milestone".into())
+            }
+            CurrEditInformation::Experience if !input.is_empty() => {
+                match input.replace(',', "").parse::<u32>() {
+                    Ok(xp) => Ok(xp.to_string()),
+                    Err(_) => Err("Experience must be a number of XP or Milestone".into()),
+                }
+            }
+            _ => Ok(input.to_string()),
         }
+    }
 
-        SavingThrowView {
-            name,
-            value,
-            proficient,
+    fn store(self, info: &mut Information, value: String) {
+        match self {
+            CurrEditInformation::CharacterName => info.character_name = value,
+            CurrEditInformation::Class => info.class = value,
+            CurrEditInformation::Level => info.level = value.parse().unwrap_or(info.level),
+            CurrEditInformation::Background => info.background = value,
+            CurrEditInformation::PlayerName => info.player_name = value,
+            CurrEditInformation::Race => info.race = value,
+            CurrEditInformation::Alignment => info.alignment = value,
+            CurrEditInformation::Experience => info.experience = value,
         }
     }
+
+    /// The field `step` places away in layout order, clamped to the ends.
+    pub fn offset(self, step: isize) -> CurrEditInformation {
+        let index = Self::ALL
+            .iter()
+            .position(|field| *field == self)
+            .unwrap_or(0);
+        let last = Self::ALL.len() as isize - 1;
+        Self::ALL[(index as isize + step).clamp(0, last) as usize]
+    }
 }
 
-#[derive(Clone, Copy)]
-pub struct SkillsView {
-    pub name: &'static str,
-    pub value: i8,
-    pub sp: SkillProficiency,
+/// The ability `step` boxes away from `ability` in layout order, clamped to
+/// the ends. The boxes are laid out two per row.
+pub fn offset_ability(ability: Ability, step: isize) -> Ability {
+    let index = Ability::ALL
+        .iter()
+        .position(|other| *other == ability)
+        .unwrap_or(0);
+    let last = Ability::ALL.len() as isize - 1;
+    Ability::ALL[(index as isize + step).clamp(0, last) as usize]
 }
 
-impl Skills {
-    pub fn skills_views(&self) -> [SkillsView; 18] {
-        [
-            Self::skills(
-                "Acrobatics (Dex)",
-                self.acrobatics.clone(),
-                self.acrobatics_skill.clone(),
-            ),
-            Self::skills(
-                "Animal Handling (Wis)",
-                self.animal_handling.clone(),
-                self.animal_handling_skill.clone(),
-            ),
-            Self::skills(
-                "Arcana (Int)",
-                self.arcana.clone(),
-                self.arcana_skill.clone(),
-            ),
-            Self::skills(
-                "Athletics (Str)",
-                self.athletics.clone(),
-                self.athletics_skill.clone(),
-            ),
-            Self::skills(
-                "Deception (Dex)",
-                self.deception.clone(),
-                self.deception_skill.clone(),
-            ),
-            Self::skills(
-                "History (Int)",
-                self.history.clone(),
-                self.history_skill.clone(),
-            ),
-            Self::skills(
-                "Insight (Wis)",
-                self.insight.clone(),
-                self.insight_skill.clone(),
-            ),
-            Self::skills(
-                "Intimidation (Cha)",
-                self.intimidation.clone(),
-                self.intimidation_skill.clone(),
-            ),
-            Self::skills(
-                "Investigation (Int)",
-                self.investigation.clone(),
-                self.investigation_skill.clone(),
-            ),
-            Self::skills(
-                "Medicine (Wis)",
-                self.medicine.clone(),
-                self.medicine_skill.clone(),
-            ),
-            Self::skills(
-                "Nature (Int)",
-                self.nature.clone(),
-                self.nature_skill.clone(),
-            ),
-            Self::skills(
-                "Perception (Wis)",
-                self.perception.clone(),
-                self.perception_skill.clone(),
-            ),
-            Self::skills(
-                "Performance (Cha)",
-                self.performance.clone(),
-                self.performance_skill.clone(),
-            ),
-            Self::skills(
-                "Persuasion (Cha)",
-                self.persuasion.clone(),
-                self.persuasion_skill.clone(),
-            ),
-            Self::skills(
-                "Religion (Int)",
-                self.religion.clone(),
-                self.religion_skill.clone(),
-            ),
-            Self::skills(
-                "Slight of Hand (Dex)",
-                self.slight_of_hand.clone(),
-                self.slight_of_hand_skill.clone(),
-            ),
-            Self::skills(
-                "Stealth (Dex)",
-                self.stealth.clone(),
-                self.stealth_skill.clone(),
-            ),
-            Self::skills(
-                "Survival (Wis)",
-                self.survival.clone(),
-                self.survival_skill.clone(),
-            ),
-        ]
-    }
+/// A saving throw or skill whose proficiency can be changed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ProficiencyTarget {
+    Save(Ability),
+    Skill(Skill),
+}
 
-    fn skills(name: &'static str, score: String, proficient_str: String) -> SkillsView {
-        let value;
-        let mut sp: SkillProficiency = SkillProficiency::None;
+impl ProficiencyTarget {
+    // The skills are drawn in two columns next to the saving throws.
+    const SKILLS_PER_COLUMN: usize = Skill::ALL.len() / 2;
 
-        if score == "" {
-            value = 0;
-        } else {
-            let value_opt = parse_string(&score);
-            match value_opt {
-                Some(val) => {
-                    value = val;
-                }
-                None => {
-                    value = 0;
-                }
+    /// Column and row of the target as drawn: saving throws in column 0,
+    /// skills in columns 1 and 2.
+    fn position(self) -> (usize, usize) {
+        match self {
+            ProficiencyTarget::Save(ability) => (
+                0,
+                Ability::ALL.iter().position(|a| *a == ability).unwrap_or(0),
+            ),
+            ProficiencyTarget::Skill(skill) => {
+                let index = Skill::ALL.iter().position(|s| *s == skill).unwrap_or(0);
+                (
+                    1 + index / Self::SKILLS_PER_COLUMN,
+                    index % Self::SKILLS_PER_COLUMN,
+                )
             }
         }
+    }
 
-        if proficient_str == "proficient" {
-            sp = SkillProficiency::Proficient;
-        } else if proficient_str == "expertise" {
-            sp = SkillProficiency::Expertise;
+    fn at(column: usize, row: usize) -> ProficiencyTarget {
+        match column {
+            0 => ProficiencyTarget::Save(Ability::ALL[row.min(Ability::ALL.len() - 1)]),
+            _ => {
+                let index = (column - 1) * Self::SKILLS_PER_COLUMN + row;
+                ProficiencyTarget::Skill(Skill::ALL[index.min(Skill::ALL.len() - 1)])
+            }
         }
-
-        SkillsView { name, value, sp }
     }
-}
 
-#[derive(Clone, Copy)]
-pub enum SkillProficiency {
-    None,
-    Proficient,
-    Expertise,
-}
-
-impl SkillProficiency {
-    pub fn symbol(self) -> &'static str {
+    pub fn roll_target(self) -> RollTarget {
         match self {
-            SkillProficiency::None => "○",
-            SkillProficiency::Proficient => "●",
-            SkillProficiency::Expertise => "◎",
+            ProficiencyTarget::Save(ability) => RollTarget::Save(ability),
+            ProficiencyTarget::Skill(skill) => RollTarget::Skill(skill),
         }
     }
+
+    /// The target `rows` up or down and `columns` left or right, clamped to
+    /// the edges of the panel.
+    pub fn offset(self, columns: isize, rows: isize) -> ProficiencyTarget {
+        let (column, row) = self.position();
+        let column = (column as isize + columns).clamp(0, 2) as usize;
+        let height = match column {
+            0 => Ability::ALL.len(),
+            _ => Self::SKILLS_PER_COLUMN,
+        };
+        let row = (row as isize + rows).clamp(0, height as isize - 1) as usize;
+        ProficiencyTarget::at(column, row)
+    }
 }
 
-fn parse_string(s: &str) -> Option<i8> {
-    match s.parse::<i8>() {
-        Ok(num) => Some(num),
-        Err(e) => {
-            println!("Failed to parse \"{}\": {}", s, e);
-            None
+/// The check `rows` up or down and `columns` left or right of `target` in
+/// roll mode: the ability boxes form the first column, then the saving
+/// throws and the two columns of skills.
+pub fn offset_roll_target(target: RollTarget, columns: isize, rows: isize) -> RollTarget {
+    let (column, row) = match target {
+        RollTarget::Ability(ability) => (0, ability as usize),
+        RollTarget::Initiative => (0, Ability::Dexterity as usize),
+        RollTarget::Save(ability) => (1, ability as usize),
+        RollTarget::Skill(skill) => {
+            let (column, row) = ProficiencyTarget::Skill(skill).position();
+            (column + 1, row)
         }
+    };
+    let column = (column as isize + columns).clamp(0, 3) as usize;
+    let row = row as isize + rows;
+    match column {
+        0 => RollTarget::Ability(
+            Ability::ALL[row.clamp(0, Ability::ALL.len() as isize - 1) as usize],
+        ),
+        _ => ProficiencyTarget::Save(Ability::Strength)
+            .offset(column as isize - 1, 0)
+            .offset(0, row)
+            .roll_target(),
     }
 }
 
-#[derive(Default)]
-pub struct ViewState {
-    pub health: HealthView,
-    // TODO: Move other View's into this structure
-    // stats_view, skills_view, etc
+/// One roll made this session, newest last in `App::roll_log`.
+pub struct RollEntry {
+    pub label: String,
+    pub expression: String,
+    pub roll: Roll,
 }
 
-#[derive(Default)]
-pub enum Hover {
-    Minus,
-    Plus,
-    #[default]
-    None,
+#[derive(Clone, Copy)]
+pub enum PathAction {
+    Open,
+    New,
 }
 
-#[derive(Default)]
-pub struct HealthView {
-    pub minus_rect: Rect,
-    pub plus_rect: Rect,
-    pub hover: Hover,
+/// The file name being typed on the load error screen.
+pub struct PathPrompt {
+    pub action: PathAction,
+    pub input: TextInput,
 }
 
-pub enum CurrentScreen {
-    Main,
-    Exiting,
+/// An amount of damage, healing or temporary HP being typed in the health
+/// panel.
+pub struct HpPrompt {
+    pub kind: HpChangeKind,
+    pub input: TextInput,
 }
 
-#[allow(dead_code)]
-pub enum CurrEditInformation {
-    CharacterName,
-    Class,
-    Level,
-    Background,
-    PlayerName,
-    Race,
-    Alignment,
-    Experience,
-    Value,
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MoneyKind {
+    Pay,
+    Receive,
+}
+
+/// An amount of money being typed on the Wallet screen, with an optional
+/// note after a `;`.
+pub struct MoneyPrompt {
+    pub kind: MoneyKind,
+    pub input: TextInput,
 }
 
 pub struct App {
     pub current_screen: CurrentScreen, // the current screen the user is looking at, and will later determine what is rendered.
     pub char_sheet: CharSheet,
     pub json_file_name: String,
+    pub load_error: Option<LoadError>,
+    pub path_prompt: Option<PathPrompt>,
+    pub dirty: bool, // set by every change to `char_sheet` until it is saved
+    pub status: Option<String>,
+    pub backup_count: usize,
+    pub backups: Vec<Backup>,
+    pub selected_backup: usize,
+    pub read_only: bool,
+    pub theme: Theme,
+    pub edit_field: CurrEditInformation,
+    pub edit_ability: Ability,
+    pub edit_proficiency: ProficiencyTarget,
+    pub edit_input: Option<TextInput>, // set while a new value is being typed
+    pub hp_prompt: Option<HpPrompt>,
+    pub last_hp_change: Option<HpChange>,
+    pub rest: Option<Rest>, // the rest shown on the Resting screen
+    pub roll_target: RollTarget,
+    pub roll_log: Vec<RollEntry>,
+    pub roll_log_scroll: usize, // rolls hidden above the top of the log panel
+    pub next_advantage: Option<Advantage>, // used by the next check rolled, then cleared
+    pub rng: Box<dyn DieRoller>, // every roll made in the app goes through this
+    pub session: u64,           // when the app was started, tags the rolls in the history
+    pub roll_sessions: Vec<SessionStats>,
+    pub selected_roll_session: usize, // 0 is every session together
+    pub selected_spell: usize,
+    pub cast_slot: SlotSource, // where the slot for the next spell comes from
+    pub selected_resource: usize,
+    pub selected_item: usize, // a row of `Inventory::rows`
+    pub item_prompt: Option<TextInput>,
+    pub money_prompt: Option<MoneyPrompt>,
+    pub ledger_scroll: usize, // transactions hidden above the top of the ledger
 }
 
 impl App {
-    pub fn new(json_file: String) -> App {
-        let mut file = File::open(&json_file).unwrap();
-        let mut buff = String::new();
-        file.read_to_string(&mut buff).unwrap();
-        let loaded_char_sheet: CharSheet = serde_json::from_str(&buff).unwrap();
-        App {
+    pub fn new(options: Options) -> App {
+        let mut app = App {
             current_screen: CurrentScreen::Main,
-            char_sheet: loaded_char_sheet,
-            json_file_name: json_file.clone(),
+            char_sheet: CharSheet::blank(),
+            json_file_name: options.sheet.clone(),
+            load_error: None,
+            path_prompt: None,
+            dirty: false,
+            status: None,
+            backup_count: options.backups,
+            backups: Vec::new(),
+            selected_backup: 0,
+            read_only: options.read_only,
+            theme: options.theme,
+            edit_field: CurrEditInformation::CharacterName,
+            edit_ability: Ability::Strength,
+            edit_proficiency: ProficiencyTarget::Save(Ability::Strength),
+            edit_input: None,
+            hp_prompt: None,
+            last_hp_change: None,
+            rest: None,
+            roll_target: RollTarget::Ability(Ability::Strength),
+            roll_log: Vec::new(),
+            roll_log_scroll: 0,
+            next_advantage: None,
+            rng: Box::new(Rng::new(options.seed)),
+            session: history::unix_time(SystemTime::now()),
+            roll_sessions: Vec::new(),
+            selected_roll_session: 0,
+            selected_spell: 0,
+            cast_slot: SlotSource::Level(1),
+            selected_resource: 0,
+            selected_item: 0,
+            item_prompt: None,
+            money_prompt: None,
+            ledger_scroll: 0,
+        };
+
+        if options.new {
+            app.create(options.sheet);
+        } else {
+            app.open(options.sheet);
+        }
+        app
+    }
+
+    /// Loads `json_file`, switching to the load error screen if it can't be
+    /// used. The sheet that was open before is kept in that case.
+    pub fn open(&mut self, json_file: String) {
+        match storage::load_sheet(&json_file) {
+            Ok(char_sheet) => {
+                self.char_sheet = char_sheet;
+                self.json_file_name = json_file;
+                self.dirty = false;
+                self.load_error = None;
+                self.current_screen = CurrentScreen::Main;
+            }
+            Err(err) => {
+                self.json_file_name = json_file;
+                self.load_error = Some(err);
+                self.current_screen = CurrentScreen::LoadFailed;
+            }
+        }
+    }
+
+    /// Starts a blank sheet that will be written to `json_file`. Refuses to
+    /// replace a file that is already there.
+    pub fn create(&mut self, json_file: String) {
+        if Path::new(&json_file).exists() {
+            self.json_file_name = json_file;
+            self.load_error = Some(LoadError::Io(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "a file with that name already exists",
+            )));
+            self.current_screen = CurrentScreen::LoadFailed;
+            return;
+        }
+
+        self.char_sheet = CharSheet::blank();
+        self.json_file_name = json_file;
+        // A new sheet only exists in memory until it is saved.
+        self.dirty = true;
+        self.load_error = None;
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    /// Every change to the sheet goes through here so that it is marked as
+    /// needing a save. A change that leaves the sheet as it was, such as a
+    /// payment the wallet can't cover, doesn't mark it.
+    pub fn modify(&mut self, change: impl FnOnce(&mut CharSheet)) {
+        if self.read_only {
+            self.status = Some("The sheet was opened read-only".into());
+            return;
+        }
+
+        let before = self.char_sheet.clone();
+        change(&mut self.char_sheet);
+        self.char_sheet.clamp_resources();
+        if self.char_sheet != before {
+            self.dirty = true;
+        }
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        if self.read_only {
+            return Err(read_only_error());
+        }
+
+        storage::save_sheet(&self.json_file_name, &self.char_sheet, self.backup_count)?;
+        self.dirty = false;
+        self.status = Some(format!("Saved to {}", self.json_file_name));
+        Ok(())
+    }
+
+    pub fn show_backups(&mut self) {
+        self.backups = storage::list_backups(&self.json_file_name);
+        self.selected_backup = 0;
+        self.current_screen = CurrentScreen::Backups;
+    }
+
+    /// Replaces the sheet with the selected backup and saves it straight
+    /// away. The version being replaced becomes the newest backup, so a
+    /// restore can itself be undone. Unsaved changes are in no backup, so
+    /// the restore waits until they have been saved.
+    pub fn restore_selected_backup(&mut self) -> io::Result<()> {
+        if self.read_only {
+            return Err(read_only_error());
+        }
+        if self.dirty {
+            self.status = Some("Save the changes first (^s), restoring would lose them".into());
+            return Ok(());
+        }
+        let Some(backup) = self.backups.get(self.selected_backup) else {
+            return Ok(());
+        };
+
+        self.char_sheet =
+            storage::load_sheet(&backup.path.to_string_lossy()).map_err(io::Error::other)?;
+        self.dirty = true;
+        let restored = format!("Restored backup {}", backup.index);
+        self.save()?;
+        self.status = Some(restored);
+        self.current_screen = CurrentScreen::Main;
+        Ok(())
+    }
+
+    /// Starts typing a new value for the selected information field or
+    /// ability score, depending on which is being edited.
+    pub fn begin_field_edit(&mut self) {
+        if self.read_only {
+            self.status = Some("The sheet was opened read-only".into());
+            return;
+        }
+        let current = match self.current_screen {
+            CurrentScreen::EditingAbilities => self
+                .char_sheet
+                .statistics
+                .score(self.edit_ability)
+                .to_string(),
+            _ => self.edit_field.value(&self.char_sheet.information),
+        };
+        self.edit_input = Some(TextInput::new(&current));
+    }
+
+    /// Stores the typed value if it is valid. On failure the input stays
+    /// open so the value can be corrected.
+    pub fn commit_field_edit(&mut self) {
+        let Some(input) = &self.edit_input else {
+            return;
+        };
+
+        if let CurrentScreen::EditingAbilities = self.current_screen {
+            match validate_score(input.value()) {
+                Ok(score) => {
+                    let ability = self.edit_ability;
+                    self.modify(|sheet| sheet.statistics.set_score(ability, score));
+                    self.edit_input = None;
+                }
+                Err(message) => self.status = Some(message),
+            }
+            return;
+        }
+
+        match self.edit_field.validate(input.value()) {
+            Ok(value) => {
+                let field = self.edit_field;
+                self.modify(|sheet| field.store(&mut sheet.information, value));
+                self.edit_input = None;
+            }
+            Err(message) => self.status = Some(message),
+        }
+    }
+
+    /// Raises or lowers the selected ability score by `step`, staying within
+    /// the range the rules allow.
+    pub fn adjust_ability(&mut self, step: i8) {
+        let ability = self.edit_ability;
+        let score = self.char_sheet.statistics.score(ability);
+        let adjusted = score
+            .saturating_add_signed(step)
+            .clamp(rules::MIN_ABILITY_SCORE, rules::MAX_ABILITY_SCORE);
+        if adjusted == score {
+            return;
+        }
+        self.modify(|sheet| sheet.statistics.set_score(ability, adjusted));
+    }
+
+    /// Opens the health panel prompt for an amount of `kind`.
+    pub fn prompt_hp(&mut self, kind: HpChangeKind) {
+        if self.read_only {
+            self.status = Some("The sheet was opened read-only".into());
+            return;
+        }
+        self.hp_prompt = Some(HpPrompt {
+            kind,
+            input: TextInput::default(),
+        });
+    }
+
+    /// Applies the typed amount, which may be a plain number or dice such as
+    /// `2d6+3`. On failure the prompt stays open so it can be corrected.
+    pub fn commit_hp_prompt(&mut self) {
+        let Some(prompt) = &self.hp_prompt else {
+            return;
+        };
+        let kind = prompt.kind;
+        let expression = prompt.input.value().trim().to_string();
+
+        let (amount, rolled) = match expression.parse::<u32>() {
+            Ok(amount) => (amount, None),
+            Err(_) => {
+                let label = match kind {
+                    HpChangeKind::Damage => "Damage",
+                    HpChangeKind::Healing => "Healing",
+                    HpChangeKind::TemporaryHp => "Temp HP",
+                };
+                match self.roll(label, &expression) {
+                    Some(roll) => (roll.total.max(0) as u32, Some(roll)),
+                    None => return,
+                }
+            }
+        };
+
+        self.hp_prompt = None;
+        self.change_hp(kind, amount);
+        if let Some(roll) = rolled {
+            self.status = Some(format!(
+                "Rolled {expression}: {} = {}",
+                roll.breakdown, roll.total
+            ));
+        }
+    }
+
+    /// Applies damage, healing or temporary HP and remembers what happened
+    /// so the health panel can show it.
+    pub fn change_hp(&mut self, kind: HpChangeKind, amount: u32) {
+        if kind == HpChangeKind::Healing
+            && self.char_sheet.health.life_state() == LifeState::Dead
+            && !self.read_only
+        {
+            self.status = Some("The character has died, healing can't bring them back".into());
+            return;
+        }
+        let mut change = None;
+        self.modify(|sheet| {
+            change = Some(match kind {
+                HpChangeKind::Damage => sheet.health.take_damage(amount),
+                HpChangeKind::Healing => sheet.health.heal(amount),
+                HpChangeKind::TemporaryHp => sheet.health.set_temporary_hp(amount),
+            });
+        });
+        if change.is_some() {
+            self.last_hp_change = change;
+        }
+    }
+
+    /// Rolls a d20 death saving throw for a dying character.
+    pub fn roll_death_save(&mut self) {
+        if self.read_only {
+            self.status = Some("The sheet was opened read-only".into());
+            return;
+        }
+        let Some(roll) = self.roll("Death save", "1d20") else {
+            return;
+        };
+        let die = roll.total as u32;
+        let mut outcome = None;
+        self.modify(|sheet| outcome = sheet.health.roll_death_save(die));
+        if let Some(outcome) = outcome {
+            self.status = Some(format!(
+                "Death save: rolled {die}, {}",
+                self.describe_death_save(outcome)
+            ));
+        }
+    }
+
+    /// Marks a death save rolled at the table.
+    pub fn record_death_save(&mut self, success: bool) {
+        let mut outcome = None;
+        self.modify(|sheet| outcome = sheet.health.record_death_save(success));
+        if let Some(outcome) = outcome {
+            self.status = Some(format!("Death save: {}", self.describe_death_save(outcome)));
+        }
+    }
+
+    fn describe_death_save(&self, outcome: DeathSaveOutcome) -> String {
+        let saves = self.char_sheet.health.death_saves;
+        match outcome {
+            DeathSaveOutcome::Success => format!("success ({}/3)", saves.successes),
+            DeathSaveOutcome::Failure => format!("failure ({}/3)", saves.failures),
+            DeathSaveOutcome::DoubleFailure => {
+                format!("natural 1, two failures ({}/3)", saves.failures)
+            }
+            DeathSaveOutcome::Revived => "natural 20! Back up with 1 HP".into(),
+            DeathSaveOutcome::Stabilized => "third success, the character is stable".into(),
+            DeathSaveOutcome::Died => "third failure, the character has died".into(),
+        }
+    }
+
+    /// Opens the rest dialog, unless the character can't rest right now.
+    pub fn start_rest(&mut self, kind: RestKind) {
+        if self.read_only {
+            self.status = Some("The sheet was opened read-only".into());
+            return;
+        }
+        let rest = Rest::new(kind);
+        if let Err(message) = rest.check(&self.char_sheet) {
+            self.status = Some(message);
+            return;
+        }
+        self.rest = Some(rest);
+        self.current_screen = CurrentScreen::Resting;
+    }
+
+    /// Rolls one more hit die on a short rest. Nothing is written to the
+    /// sheet until the rest is finished.
+    pub fn spend_hit_die(&mut self) {
+        let Some(rest) = &mut self.rest else {
+            return;
+        };
+        if !rest.can_spend_hit_die(&self.char_sheet) {
+            return;
+        }
+        let Some(sides) = self.char_sheet.health.hit_die_sides() else {
+            return;
+        };
+        if let Some(roll) = self.roll("Hit die", &format!("1d{sides}"))
+            && let Some(rest) = &mut self.rest
+        {
+            rest.hit_die_rolls.push(roll.total as u32);
+        }
+    }
+
+    pub fn undo_hit_die(&mut self) {
+        if let Some(rest) = &mut self.rest {
+            rest.hit_die_rolls.pop();
+        }
+    }
+
+    pub fn finish_rest(&mut self) {
+        let Some(rest) = self.rest.take() else {
+            return;
+        };
+        let mut summary = None;
+        self.modify(|sheet| summary = Some(rest.apply(sheet)));
+        if let Some(summary) = summary {
+            self.last_hp_change = None;
+            self.status = Some(format!(
+                "{} finished: HP {} -> {}, hit dice {} -> {}",
+                rest.kind.label(),
+                summary.hp_before,
+                summary.hp_after,
+                summary.hit_dice_before,
+                summary.hit_dice_after
+            ));
+        }
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    pub fn cancel_rest(&mut self) {
+        self.rest = None;
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    /// Rolls a d20 check for `target`. Without an `advantage` of its own the
+    /// check uses whatever `cycle_advantage` set up.
+    pub fn roll_check(&mut self, target: RollTarget, advantage: Option<Advantage>) {
+        let advantage = advantage.or(self.next_advantage.take());
+        let modifier = self.char_sheet.check_modifier(target);
+        let expression = dice::check_expression(modifier, advantage);
+        self.roll(&target.label(), &expression);
+    }
+
+    /// Rolls `expression`, which may refer to the sheet, and records the
+    /// result in the roll log and the roll history file. Shows the error
+    /// and returns `None` if the expression can't be rolled.
+    fn roll(&mut self, label: &str, expression: &str) -> Option<Roll> {
+        let roll = match dice::roll_expression(expression, &mut self.rng, Some(&self.char_sheet)) {
+            Ok(roll) => roll,
+            Err(err) => {
+                self.status = Some(err.to_string());
+                return None;
+            }
+        };
+
+        // A read-only sheet may well be someone else's, so its history is
+        // left alone.
+        if !self.read_only {
+            let entry = HistoryEntry::new(self.session, label, expression, &roll);
+            if let Err(err) = history::append(&self.json_file_name, &entry) {
+                self.status = Some(format!("Could not write the roll history: {err}"));
+            }
+        }
+
+        self.roll_log.push(RollEntry {
+            label: label.to_string(),
+            expression: expression.to_string(),
+            roll: roll.clone(),
+        });
+        // Jump back to the newest roll.
+        self.roll_log_scroll = 0;
+        Some(roll)
+    }
+
+    /// Steps the next check between a plain roll, advantage and
+    /// disadvantage, for clicking without holding Shift or Ctrl.
+    pub fn cycle_advantage(&mut self) {
+        self.next_advantage = match self.next_advantage {
+            None => Some(Advantage::Advantage),
+            Some(Advantage::Advantage) => Some(Advantage::Disadvantage),
+            Some(Advantage::Disadvantage) => None,
+        };
+    }
+
+    /// Reads the roll history of the sheet and opens the statistics screen.
+    pub fn show_roll_stats(&mut self) {
+        match history::load(&self.json_file_name) {
+            Ok(entries) => {
+                self.roll_sessions = history::session_stats(&entries);
+                self.selected_roll_session = 0;
+                self.current_screen = CurrentScreen::RollStats;
+            }
+            Err(err) => self.status = Some(format!("Could not read the roll history: {err}")),
+        }
+    }
+
+    /// Scrolls the roll log by `step` entries, positive going back in time.
+    pub fn scroll_roll_log(&mut self, step: isize) {
+        let oldest = self.roll_log.len().saturating_sub(1);
+        self.roll_log_scroll = self.roll_log_scroll.saturating_add_signed(step).min(oldest);
+    }
+
+    /// Toggles a saving throw proficiency or moves a skill on to its next
+    /// proficiency level.
+    pub fn toggle_proficiency(&mut self, target: ProficiencyTarget) {
+        self.modify(|sheet| match target {
+            ProficiencyTarget::Save(ability) => sheet.saving_throws.toggle(ability),
+            ProficiencyTarget::Skill(skill) => sheet.skills.cycle(skill),
+        });
+    }
+
+    pub fn show_spells(&mut self) {
+        self.current_screen = CurrentScreen::Spells;
+        self.select_spell(self.selected_spell);
+    }
+
+    pub fn move_spell(&mut self, step: isize) {
+        self.select_spell(self.selected_spell.saturating_add_signed(step));
+    }
+
+    /// Selects spell `index` and starts out casting it at its own level,
+    /// from the pact slots when the character only has those.
+    fn select_spell(&mut self, index: usize) {
+        let Some(spellcasting) = &self.char_sheet.spellcasting else {
+            self.selected_spell = 0;
+            return;
+        };
+        let spells = &spellcasting.spells;
+        self.selected_spell = index.min(spells.len().saturating_sub(1));
+        if let Some(spell) = spells.get(self.selected_spell) {
+            let level = spell.level.max(1);
+            let pact_fits = spellcasting
+                .pact_magic
+                .is_some_and(|pact| pact.level >= level);
+            self.cast_slot = if pact_fits && spellcasting.slots(level).max == 0 {
+                SlotSource::Pact
+            } else {
+                SlotSource::Level(level)
+            };
+        }
+    }
+
+    /// Steps through the standard slot levels and then the pact slots.
+    pub fn shift_cast_slot(&mut self, step: isize) {
+        let pact = rules::MAX_SPELL_LEVEL as isize;
+        let current = match self.cast_slot {
+            SlotSource::Level(level) => level as isize - 1,
+            SlotSource::Pact => pact,
+        };
+        self.cast_slot = match (current + step).clamp(0, pact) {
+            index if index == pact => SlotSource::Pact,
+            index => SlotSource::Level(index as u8 + 1),
+        };
+    }
+
+    /// Casts the selected spell with a slot from `cast_slot`, unless it is
+    /// a cantrip.
+    pub fn cast_selected_spell(&mut self) {
+        let (index, source) = (self.selected_spell, self.cast_slot);
+        let mut result = None;
+        self.modify(|sheet| {
+            if let Some(spellcasting) = &mut sheet.spellcasting {
+                result = Some(spellcasting.cast(index, source));
+            }
+        });
+        let Some(result) = result else {
+            return;
+        };
+
+        let Some(spell) = self
+            .char_sheet
+            .spellcasting
+            .as_ref()
+            .and_then(|spellcasting| spellcasting.spells.get(index))
+        else {
+            return;
+        };
+        let slot_name = |slot| match source {
+            SlotSource::Pact => format!("a pact slot ({} level)", spells::level_name(slot)),
+            SlotSource::Level(_) => format!("a {} level slot", spells::level_name(slot)),
+        };
+        self.status = Some(match result {
+            Ok(None) => format!("Cast {}", spell.name),
+            Ok(Some(slot)) if slot > spell.level => {
+                format!("Cast {} upcast with {}", spell.name, slot_name(slot))
+            }
+            Ok(Some(slot)) => format!("Cast {} with {}", spell.name, slot_name(slot)),
+            Err(message) => message,
+        });
+    }
+
+    pub fn toggle_prepared(&mut self) {
+        if let Some(spellcasting) = &self.char_sheet.spellcasting
+            && spellcasting.preparation == Preparation::Known
+        {
+            self.status = Some("This caster can cast any spell they know, (P) to change".into());
+            return;
+        }
+        let index = self.selected_spell;
+        self.modify(|sheet| {
+            if let Some(spell) = sheet
+                .spellcasting
+                .as_mut()
+                .and_then(|spellcasting| spellcasting.spells.get_mut(index))
+            {
+                spell.prepared = !spell.prepared;
+            }
+        });
+    }
+
+    /// Switches between casting prepared spells and casting any known spell.
+    pub fn toggle_preparation(&mut self) {
+        self.modify(|sheet| {
+            if let Some(spellcasting) = &mut sheet.spellcasting {
+                spellcasting.preparation = match spellcasting.preparation {
+                    Preparation::Prepared => Preparation::Known,
+                    Preparation::Known => Preparation::Prepared,
+                };
+            }
+        });
+    }
+
+    /// Gives back one expended slot from `cast_slot`, e.g. one spent by
+    /// mistake or recovered by a class feature.
+    pub fn regain_slot(&mut self) {
+        let source = self.cast_slot;
+        let mut regained = false;
+        self.modify(|sheet| {
+            if let Some(spellcasting) = &mut sheet.spellcasting {
+                regained = spellcasting.regain_slot(source);
+            }
+        });
+        if !regained && !self.read_only {
+            self.status = Some(match source {
+                SlotSource::Level(level) => {
+                    format!("No {} level slots to regain", spells::level_name(level))
+                }
+                SlotSource::Pact => "No pact slots to regain".into(),
+            });
+        }
+    }
+
+    pub fn adjust_max_slots(&mut self, step: i8) {
+        let source = self.cast_slot;
+        self.modify(|sheet| {
+            if let Some(spellcasting) = &mut sheet.spellcasting {
+                let max = spellcasting
+                    .slots_of(source)
+                    .max
+                    .saturating_add_signed(step);
+                match source {
+                    SlotSource::Level(level) => spellcasting.set_max_slots(level, max),
+                    SlotSource::Pact => spellcasting.set_max_pact_slots(max),
+                }
+            }
+        });
+    }
+
+    pub fn adjust_pact_level(&mut self, step: i8) {
+        self.modify(|sheet| {
+            if let Some(spellcasting) = &mut sheet.spellcasting
+                && let Some(level) = spellcasting.slot_level(SlotSource::Pact)
+            {
+                spellcasting.set_pact_level(level.saturating_add_signed(step));
+            }
+        });
+    }
+
+    /// Moves the casting ability on to the next one, giving the sheet a
+    /// spellcasting section first if it has none.
+    pub fn cycle_casting_ability(&mut self) {
+        self.modify(|sheet| match &mut sheet.spellcasting {
+            Some(spellcasting) => {
+                let index = spellcasting.ability as usize;
+                spellcasting.ability = Ability::ALL[(index + 1) % Ability::ALL.len()];
+            }
+            None => sheet.spellcasting = Some(Spellcasting::new(Ability::Intelligence)),
+        });
+    }
+
+    pub fn show_resources(&mut self) {
+        let count = self.char_sheet.resources.len();
+        if count == 0 {
+            self.status =
+                Some("This sheet tracks no resources; add them under \"resources\"".into());
+            return;
+        }
+        self.selected_resource = self.selected_resource.min(count - 1);
+        self.current_screen = CurrentScreen::Resources;
+    }
+
+    pub fn move_resource(&mut self, step: isize) {
+        let last = self.char_sheet.resources.len().saturating_sub(1);
+        self.selected_resource = self.selected_resource.saturating_add_signed(step).min(last);
+    }
+
+    /// Uses one of resource `index`, selecting it.
+    pub fn spend_resource(&mut self, index: usize) {
+        self.selected_resource = index;
+        let mut spent = None;
+        self.modify(|sheet| {
+            spent = sheet
+                .resources
+                .get_mut(index)
+                .map(|resource| (resource.name.clone(), resource.spend()));
+        });
+        if let Some((name, false)) = spent {
+            self.status = Some(format!("No {name} left"));
+        }
+    }
+
+    /// Gets one of resource `index` back, selecting it.
+    pub fn restore_resource(&mut self, index: usize) {
+        self.selected_resource = index;
+        let mut restored = None;
+        self.modify(|sheet| {
+            let Some(max) = sheet.resources.get(index).map(|r| r.max(sheet)) else {
+                return;
+            };
+            let resource = &mut sheet.resources[index];
+            restored = Some((resource.name.clone(), resource.restore(max)));
+        });
+        if let Some((name, false)) = restored {
+            self.status = Some(format!("{name} is already full"));
+        }
+    }
+
+    /// Fills the selected resource up without waiting for a rest.
+    pub fn refill_resource(&mut self) {
+        let index = self.selected_resource;
+        self.modify(|sheet| {
+            if let Some(max) = sheet.resources.get(index).map(|r| r.max(sheet)) {
+                sheet.resources[index].refill(max);
+            }
+        });
+    }
+
+    pub fn show_inventory(&mut self) {
+        self.current_screen = CurrentScreen::Inventory;
+        self.move_item_selection(0);
+    }
+
+    pub fn move_item_selection(&mut self, step: isize) {
+        let last = self.char_sheet.inventory.rows().len().saturating_sub(1);
+        self.selected_item = self.selected_item.saturating_add_signed(step).min(last);
+    }
+
+    fn selected_item_path(&self) -> Option<Vec<usize>> {
+        self.char_sheet
+            .inventory
+            .rows()
+            .into_iter()
+            .nth(self.selected_item)
+            .map(|(path, _)| path)
+    }
+
+    fn select_item_path(&mut self, path: &[usize]) {
+        if let Some(row) = self
+            .char_sheet
+            .inventory
+            .rows()
+            .iter()
+            .position(|(row, _)| row == path)
+        {
+            self.selected_item = row;
+        }
+    }
+
+    pub fn prompt_item(&mut self) {
+        if self.read_only {
+            self.status = Some("The sheet was opened read-only".into());
+            return;
+        }
+        self.item_prompt = Some(TextInput::new(""));
+    }
+
+    /// Adds the item typed into the prompt to the items carried.
+    pub fn commit_item_prompt(&mut self) {
+        let Some(prompt) = &self.item_prompt else {
+            return;
+        };
+        let item = match Item::parse(prompt.value()) {
+            Ok(item) => item,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        self.item_prompt = None;
+        self.modify(|sheet| sheet.inventory.items.push(item));
+        let last = self.char_sheet.inventory.items.len() - 1;
+        self.select_item_path(&[last]);
+    }
+
+    pub fn adjust_item_quantity(&mut self, step: i32) {
+        let Some(path) = self.selected_item_path() else {
+            return;
+        };
+        self.modify(|sheet| {
+            if let Some(item) = sheet.inventory.get_mut(&path) {
+                item.quantity = item.quantity.saturating_add_signed(step).max(1);
+            }
+        });
+    }
+
+    pub fn remove_item(&mut self) {
+        let Some(path) = self.selected_item_path() else {
+            return;
+        };
+        let mut removed = None;
+        self.modify(|sheet| removed = sheet.inventory.remove(&path));
+        if let Some(item) = removed {
+            self.status = Some(format!("Removed {}", item.name));
+            self.move_item_selection(0);
+        }
+    }
+
+    /// Moves the selected item on to the next container it fits in, or
+    /// back out to being carried.
+    pub fn move_item(&mut self) {
+        let Some(path) = self.selected_item_path() else {
+            return;
+        };
+        let inventory = &self.char_sheet.inventory;
+        let name = inventory.get(&path).map(|item| item.name.clone());
+        let Some(destination) = inventory.next_destination(&path) else {
+            self.status = Some(format!(
+                "Nothing else has room for {}",
+                name.unwrap_or_default()
+            ));
+            return;
+        };
+        let into = inventory
+            .get(&destination)
+            .map_or("carried items".to_string(), |item| item.name.clone());
+
+        let mut moved = None;
+        self.modify(|sheet| moved = sheet.inventory.move_item(&path, &destination));
+        if let Some(path) = moved {
+            self.select_item_path(&path);
+            self.status = Some(format!("Moved {} to {into}", name.unwrap_or_default()));
+        }
+    }
+
+    pub fn toggle_equipped(&mut self) {
+        let Some(path) = self.selected_item_path() else {
+            return;
+        };
+        self.modify(|sheet| {
+            if let Some(item) = sheet.inventory.get_mut(&path) {
+                item.equipped = !item.equipped;
+            }
+        });
+    }
+
+    pub fn toggle_attuned(&mut self) {
+        let Some(path) = self.selected_item_path() else {
+            return;
+        };
+        let inventory = &self.char_sheet.inventory;
+        if inventory.get(&path).is_some_and(|item| !item.attuned)
+            && inventory.attuned() >= rules::MAX_ATTUNED_ITEMS
+        {
+            self.status = Some(format!(
+                "No more than {} items can be attuned",
+                rules::MAX_ATTUNED_ITEMS
+            ));
+            return;
+        }
+        self.modify(|sheet| {
+            if let Some(item) = sheet.inventory.get_mut(&path) {
+                item.attuned = !item.attuned;
+            }
+        });
+    }
+
+    /// Makes the selected item a container, or an empty container a plain
+    /// item again.
+    pub fn toggle_container(&mut self) {
+        let Some(path) = self.selected_item_path() else {
+            return;
+        };
+        if let Some(item) = self.char_sheet.inventory.get(&path)
+            && item
+                .container
+                .as_ref()
+                .is_some_and(|container| !container.contents.is_empty())
+        {
+            self.status = Some(format!("Empty {} first", item.name));
+            return;
+        }
+        self.modify(|sheet| {
+            if let Some(item) = sheet.inventory.get_mut(&path) {
+                item.container = match item.container {
+                    Some(_) => None,
+                    None => Some(Container::for_item(&item.name)),
+                };
+            }
+        });
+    }
+
+    pub fn toggle_variant_encumbrance(&mut self) {
+        self.modify(|sheet| {
+            sheet.inventory.variant_encumbrance = !sheet.inventory.variant_encumbrance;
+        });
+    }
+
+    pub fn show_wallet(&mut self) {
+        self.current_screen = CurrentScreen::Wallet;
+        self.ledger_scroll = 0;
+    }
+
+    pub fn scroll_ledger(&mut self, step: isize) {
+        let last = self.char_sheet.wallet.ledger.len().saturating_sub(1);
+        self.ledger_scroll = self.ledger_scroll.saturating_add_signed(step).min(last);
+    }
+
+    pub fn prompt_money(&mut self, kind: MoneyKind) {
+        if self.read_only {
+            self.status = Some("The sheet was opened read-only".into());
+            return;
+        }
+        self.money_prompt = Some(MoneyPrompt {
+            kind,
+            input: TextInput::default(),
+        });
+    }
+
+    /// Pays or receives the typed amount. On failure the prompt stays open
+    /// so it can be corrected.
+    pub fn commit_money_prompt(&mut self) {
+        let Some(prompt) = &self.money_prompt else {
+            return;
+        };
+        let kind = prompt.kind;
+        let (amount, note) = prompt
+            .input
+            .value()
+            .split_once(';')
+            .unwrap_or((prompt.input.value(), ""));
+        let note = note.trim().to_string();
+        let coins = match wallet::parse_amount(amount) {
+            Ok(coins) => coins,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+
+        let status = match kind {
+            MoneyKind::Pay => {
+                let mut result = None;
+                self.modify(|sheet| {
+                    result = Some(sheet.wallet.pay(wallet::total_cp(&coins), &note))
+                });
+                match result {
+                    Some(Ok(payment)) if payment.change.is_empty() => {
+                        format!("Paid {}", wallet::format_coins(&payment.paid))
+                    }
+                    Some(Ok(payment)) => format!(
+                        "Paid {} and got {} in change",
+                        wallet::format_coins(&payment.paid),
+                        wallet::format_coins(&payment.change)
+                    ),
+                    Some(Err(err)) => {
+                        self.status = Some(err);
+                        return;
+                    }
+                    None => return,
+                }
+            }
+            MoneyKind::Receive => {
+                let mut received = false;
+                self.modify(|sheet| {
+                    sheet.wallet.receive(&coins, &note);
+                    received = true;
+                });
+                if !received {
+                    return;
+                }
+                format!("Received {}", wallet::format_coins(&coins))
+            }
+        };
+        self.money_prompt = None;
+        self.ledger_scroll = 0;
+        self.status = Some(status);
+    }
+
+    pub fn consolidate_coins(&mut self) {
+        let mut changed = false;
+        self.modify(|sheet| changed = sheet.wallet.consolidate());
+        if !changed && !self.read_only {
+            self.status = Some("The coins can't be made any fewer".into());
         }
     }
 }
 
-impl Drop for App {
-    fn drop(&mut self) {
-        println!("Closing the file and writing it disk");
-        // Here you'd close a file, free memory, etc.
-        // 2. Create the output file
-        let file = File::create(self.json_file_name.clone()).unwrap();
-        let mut writer = BufWriter::new(file); // Use BufWriter for performance
-        let _ = serde_json::to_writer_pretty(&mut writer, &self.char_sheet);
+fn validate_score(input: &str) -> Result<u8, String> {
+    match input.trim().parse::<u8>() {
+        Ok(score) if (rules::MIN_ABILITY_SCORE..=rules::MAX_ABILITY_SCORE).contains(&score) => {
+            Ok(score)
+        }
+        _ => Err(format!(
+            "Ability scores must be a number from {} to {}",
+            rules::MIN_ABILITY_SCORE,
+            rules::MAX_ABILITY_SCORE
+        )),
+    }
+}
+
+fn read_only_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "the sheet was opened read-only",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{self, Command};
+
+    // Only kept in memory: a new sheet isn't written until it is saved.
+    fn editing(field: CurrEditInformation) -> App {
+        let Ok(Command::Run(options)) = cli::parse(["--new".into(), "unsaved.json".into()]) else {
+            panic!("the options were refused");
+        };
+        let mut app = App::new(options);
+        app.current_screen = CurrentScreen::Editing;
+        app.edit_field = field;
+        app.begin_field_edit();
+        app
+    }
+
+    fn commit(app: &mut App, typed: &str) {
+        app.edit_input = Some(TextInput::new(typed));
+        app.commit_field_edit();
+    }
+
+    #[test]
+    fn refuses_a_level_outside_1_to_20() {
+        let mut app = editing(CurrEditInformation::Level);
+        for typed in ["0", "21", "three", ""] {
+            commit(&mut app, typed);
+            assert!(app.edit_input.is_some(), "{typed} was accepted");
+            assert_eq!(
+                app.status.as_deref(),
+                Some("Level must be a number from 1 to 20")
+            );
+        }
+        assert_eq!(app.char_sheet.information.level, 1);
+
+        commit(&mut app, " 7 ");
+        assert!(app.edit_input.is_none());
+        assert_eq!(app.char_sheet.information.level, 7);
+    }
+
+    #[test]
+    fn experience_is_a_number_or_milestone() {
+        let mut app = editing(CurrEditInformation::Experience);
+        for typed in ["-50", "lots", "1.5"] {
+            commit(&mut app, typed);
+            assert!(app.edit_input.is_some(), "{typed} was accepted");
+        }
+
+        commit(&mut app, "6,500");
+        assert_eq!(app.char_sheet.information.experience, "6500");
+        app.begin_field_edit();
+        commit(&mut app, "milestone");
+        assert_eq!(app.char_sheet.information.experience, "Milestone");
+    }
+
+    #[test]
+    fn keeps_names_as_typed_but_not_blank() {
+        let mut app = editing(CurrEditInformation::CharacterName);
+        commit(&mut app, "   ");
+        assert_eq!(app.status.as_deref(), Some("The character needs a name"));
 
-        writer.flush().unwrap();
+        commit(&mut app, "Zoë ");
+        assert_eq!(app.char_sheet.information.character_name, "Zoë");
     }
 }
@@ -0,0 +1,357 @@
+use std::fmt;
+
+use char_sheet::dice::Advantage;
+use char_sheet::storage::DEFAULT_BACKUP_COUNT;
+
+use crate::theme::Theme;
+
+pub const USAGE: &str = "\
+Usage: char-sheet [OPTIONS] <SHEET>
+       char-sheet <COMMAND> <SHEET> [ARGS]
+
+Arguments:
+  <SHEET>            Path to the character sheet JSON file
+
+Commands:
+  show <SHEET>               Print a summary of the sheet
+  get <SHEET> <KEY>          Print one value, e.g. health.current_hp
+  set <SHEET> <KEY> <VALUE>  Change one value and save the sheet
+  roll <SHEET> <CHECK>       Roll a skill, ability check (str), save
+                             (str-save) or initiative, with --adv or --dis
+                             for advantage or disadvantage
+
+Options:
+  --new              Start a blank sheet at <SHEET> instead of opening one
+  --read-only        Open the sheet without allowing changes or saves
+  --theme <THEME>    Colour theme: dark, light or mono [default: dark]
+  --backups <N>      Number of backups to keep when saving (the app and set)
+                     [default: 5]
+  --seed <N>         Seed the dice so the same actions roll the same results
+                     (the app and roll)
+  --json             Print command output as JSON (show, get, roll)
+  -h, --help         Print help
+  -V, --version      Print version
+
+Exit status:
+  0 on success, 1 if the sheet can't be read or written, 2 for usage
+  errors and 3 for an unknown key, invalid value or unknown check.
+";
+
+pub struct Options {
+    pub sheet: String,
+    pub new: bool,
+    pub read_only: bool,
+    pub theme: Theme,
+    pub backups: usize,
+    pub seed: Option<u64>,
+}
+
+/// A command that works on the sheet without opening the TUI.
+pub enum Headless {
+    Show {
+        sheet: String,
+    },
+    Get {
+        sheet: String,
+        key: String,
+    },
+    Set {
+        sheet: String,
+        key: String,
+        value: String,
+        backups: usize,
+    },
+    Roll {
+        sheet: String,
+        check: String,
+        seed: Option<u64>,
+        advantage: Option<Advantage>,
+    },
+}
+
+pub enum Command {
+    Run(Options),
+    Headless { command: Headless, json: bool },
+    Help,
+    Version,
+}
+
+#[derive(Debug)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parses the arguments that follow the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, CliError> {
+    let args: Vec<String> = args.into_iter().collect();
+    if let Some(name) = args.first()
+        && matches!(name.as_str(), "show" | "get" | "set" | "roll")
+    {
+        return parse_headless(name, &args[1..]);
+    }
+
+    let mut args = args.into_iter();
+    let mut sheet = None;
+    let mut new = false;
+    let mut read_only = false;
+    let mut theme = Theme::default();
+    let mut backups = DEFAULT_BACKUP_COUNT;
+    let mut seed = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--new" => new = true,
+            "--read-only" => read_only = true,
+            "--theme" => {
+                let name = value_for(&arg, args.next())?;
+                theme = Theme::by_name(&name).ok_or_else(|| {
+                    CliError(format!(
+                        "unknown theme '{name}', expected one of: {}",
+                        Theme::NAMES.join(", ")
+                    ))
+                })?;
+            }
+            "--backups" => backups = parse_backups(&value_for(&arg, args.next())?)?,
+            "--seed" => seed = Some(parse_seed(&value_for(&arg, args.next())?)?),
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(CliError(format!("unknown option '{flag}'")));
+            }
+            path => {
+                if sheet.is_some() {
+                    return Err(CliError(format!("unexpected argument '{path}'")));
+                }
+                sheet = Some(path.to_string());
+            }
+        }
+    }
+
+    let sheet = sheet.ok_or_else(|| CliError("no sheet file given".into()))?;
+    if new && read_only {
+        return Err(CliError(
+            "--new and --read-only cannot be used together".into(),
+        ));
+    }
+
+    Ok(Command::Run(Options {
+        sheet,
+        new,
+        read_only,
+        theme,
+        backups,
+        seed,
+    }))
+}
+
+fn parse_headless(name: &str, args: &[String]) -> Result<Command, CliError> {
+    let mut json = false;
+    let mut seed = None;
+    let mut backups = DEFAULT_BACKUP_COUNT;
+    let mut advantage = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--json" => json = true,
+            "--seed" if name == "roll" => {
+                seed = Some(parse_seed(&value_for(arg, args.next().cloned())?)?);
+            }
+            "--backups" if name == "set" => {
+                backups = parse_backups(&value_for(arg, args.next().cloned())?)?;
+            }
+            "--adv" if name == "roll" => advantage = Some(Advantage::Advantage),
+            "--dis" if name == "roll" => advantage = Some(Advantage::Disadvantage),
+            // Let negative numbers through as values for `set`.
+            flag if flag.starts_with("--") => {
+                return Err(CliError(format!("unknown option '{flag}'")));
+            }
+            value => positional.push(value.to_string()),
+        }
+    }
+
+    let expected = match name {
+        "show" => 1,
+        "get" | "roll" => 2,
+        _ => 3,
+    };
+    if positional.len() != expected {
+        return Err(CliError(format!(
+            "'{name}' expects {expected} argument(s), found {}",
+            positional.len()
+        )));
+    }
+
+    let mut positional = positional.into_iter();
+    let sheet = positional.next().unwrap_or_default();
+    let mut next = || positional.next().unwrap_or_default();
+    let command = match name {
+        "show" => Headless::Show { sheet },
+        "get" => Headless::Get { sheet, key: next() },
+        "roll" => Headless::Roll {
+            sheet,
+            check: next(),
+            seed,
+            advantage,
+        },
+        _ => Headless::Set {
+            sheet,
+            key: next(),
+            value: next(),
+            backups,
+        },
+    };
+
+    Ok(Command::Headless { command, json })
+}
+
+fn parse_seed(value: &str) -> Result<u64, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("--seed expects a number, found '{value}'")))
+}
+
+fn parse_backups(value: &str) -> Result<usize, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("--backups expects a number, found '{value}'")))
+}
+
+fn value_for(flag: &str, value: Option<String>) -> Result<String, CliError> {
+    value.ok_or_else(|| CliError(format!("{flag} expects a value")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, CliError> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn run_options(args: &[&str]) -> Options {
+        match parse_args(args) {
+            Ok(Command::Run(options)) => options,
+            Ok(_) => panic!("{args:?} didn't open the TUI"),
+            Err(err) => panic!("{args:?} was refused: {err}"),
+        }
+    }
+
+    fn error(args: &[&str]) -> String {
+        match parse_args(args) {
+            Err(err) => err.to_string(),
+            Ok(_) => panic!("{args:?} was accepted"),
+        }
+    }
+
+    #[test]
+    fn reads_options_in_any_order() {
+        let options = run_options(&["--read-only", "hero.json", "--backups", "2", "--seed", "42"]);
+        assert_eq!(options.sheet, "hero.json");
+        assert!(options.read_only && !options.new);
+        assert_eq!(options.backups, 2);
+        assert_eq!(options.seed, Some(42));
+
+        let options = run_options(&["hero.json"]);
+        assert_eq!(options.backups, DEFAULT_BACKUP_COUNT);
+        assert_eq!(options.seed, None);
+    }
+
+    #[test]
+    fn refuses_bad_arguments() {
+        assert_eq!(
+            error(&["--verbose", "hero.json"]),
+            "unknown option '--verbose'"
+        );
+        assert_eq!(error(&["hero.json", "--theme"]), "--theme expects a value");
+        assert_eq!(error(&["hero.json", "--seed"]), "--seed expects a value");
+        assert_eq!(
+            error(&["hero.json", "--seed", "lucky"]),
+            "--seed expects a number, found 'lucky'"
+        );
+        assert_eq!(
+            error(&["hero.json", "--backups", "-1"]),
+            "--backups expects a number, found '-1'"
+        );
+        assert!(error(&["hero.json", "--theme", "neon"]).starts_with("unknown theme 'neon'"));
+        assert_eq!(error(&[]), "no sheet file given");
+        assert_eq!(error(&["a.json", "b.json"]), "unexpected argument 'b.json'");
+        assert_eq!(
+            error(&["--new", "--read-only", "hero.json"]),
+            "--new and --read-only cannot be used together"
+        );
+    }
+
+    #[test]
+    fn reads_subcommands() {
+        let Ok(Command::Headless { command, json }) = parse_args(&[
+            "roll",
+            "hero.json",
+            "str-save",
+            "--seed",
+            "7",
+            "--json",
+            "--adv",
+        ]) else {
+            panic!("roll wasn't read as a subcommand");
+        };
+        assert!(json);
+        assert!(matches!(
+            command,
+            Headless::Roll {
+                sheet,
+                check,
+                seed: Some(7),
+                advantage: Some(Advantage::Advantage),
+            } if sheet == "hero.json" && check == "str-save"
+        ));
+
+        // A negative number is a value, not an option.
+        let Ok(Command::Headless { command, .. }) =
+            parse_args(&["set", "hero.json", "health.initiative_override", "-1"])
+        else {
+            panic!("set wasn't read as a subcommand");
+        };
+        assert!(matches!(
+            command,
+            Headless::Set { value, backups: DEFAULT_BACKUP_COUNT, .. } if value == "-1"
+        ));
+
+        let Ok(Command::Headless { command, .. }) = parse_args(&[
+            "set",
+            "--backups",
+            "0",
+            "hero.json",
+            "information.level",
+            "2",
+        ]) else {
+            panic!("set wasn't read as a subcommand");
+        };
+        assert!(matches!(command, Headless::Set { backups: 0, .. }));
+        assert_eq!(
+            error(&["get", "hero.json", "information.level", "--backups", "2"]),
+            "unknown option '--backups'"
+        );
+
+        assert_eq!(
+            error(&["get", "hero.json"]),
+            "'get' expects 2 argument(s), found 1"
+        );
+        assert_eq!(
+            error(&["show", "hero.json", "--seed", "7"]),
+            "unknown option '--seed'"
+        );
+        assert_eq!(
+            error(&["roll", "hero.json", "athletics", "--seed"]),
+            "--seed expects a value"
+        );
+    }
+}
@@ -0,0 +1,672 @@
+// The non-interactive subcommands (`show`, `get`, `set` and `roll`). They
+// share the sheet model, rules and storage with the TUI but only ever print
+// plain text or JSON.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use char_sheet::dice::{self, Advantage, Rng};
+use char_sheet::history::{self, HistoryEntry};
+use char_sheet::inventory;
+use char_sheet::sheet::{CharSheet, RollTarget};
+use char_sheet::spells;
+use char_sheet::storage::{self, LoadError};
+use char_sheet::wallet::{self, Coin};
+
+use crate::cli::Headless;
+
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_INVALID: i32 = 3;
+
+enum CommandError {
+    Load(LoadError),
+    Save(io::Error),
+    Invalid(String),
+}
+
+impl CommandError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CommandError::Load(_) | CommandError::Save(_) => EXIT_FAILURE,
+            CommandError::Invalid(_) => EXIT_INVALID,
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Load(err) => write!(f, "{err}"),
+            CommandError::Save(err) => write!(f, "could not save the sheet: {err}"),
+            CommandError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Something that went wrong without stopping the command.
+enum Warning {
+    History(io::Error),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::History(err) => write!(f, "could not write the roll history: {err}"),
+        }
+    }
+}
+
+/// What a command prints, plus anything it wants to warn about on stderr.
+struct Output {
+    text: String,
+    warnings: Vec<Warning>,
+}
+
+impl From<String> for Output {
+    fn from(text: String) -> Output {
+        Output {
+            text,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Runs `command` and returns the process exit status.
+pub fn run(command: Headless, json: bool) -> i32 {
+    match execute(command, json) {
+        Ok(output) => {
+            for warning in &output.warnings {
+                eprintln!("warning: {warning}");
+            }
+            // A closed pipe (`char-sheet show x | head`) is not an error.
+            let _ = writeln!(io::stdout(), "{}", output.text);
+            0
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            err.exit_code()
+        }
+    }
+}
+
+fn execute(command: Headless, json: bool) -> Result<Output, CommandError> {
+    match command {
+        Headless::Show { sheet } => {
+            let char_sheet = load(&sheet)?;
+            if json {
+                Ok(pretty(&document(&char_sheet)).into())
+            } else {
+                Ok(summary(&char_sheet).into())
+            }
+        }
+        Headless::Get { sheet, key } => {
+            let char_sheet = load(&sheet)?;
+            let document = document(&char_sheet);
+            let value = document
+                .pointer(&pointer(&key))
+                .ok_or_else(|| CommandError::Invalid(format!("no value at '{key}'")))?;
+            Ok(if json { pretty(value) } else { plain(value) }.into())
+        }
+        Headless::Set {
+            sheet,
+            key,
+            value,
+            backups,
+        } => {
+            let mut char_sheet = load(&sheet)?;
+            let value = set(&mut char_sheet, &key, &value)?;
+            storage::save_sheet(&sheet, &char_sheet, backups).map_err(CommandError::Save)?;
+            Ok(format!("{key} = {}", plain(&value)).into())
+        }
+        Headless::Roll {
+            sheet,
+            check,
+            seed,
+            advantage,
+        } => roll(&sheet, &check, seed, advantage, json),
+    }
+}
+
+fn load(sheet: &str) -> Result<CharSheet, CommandError> {
+    storage::load_sheet(sheet).map_err(CommandError::Load)
+}
+
+/// `health.current_hp` -> `/health/current_hp`
+fn pointer(key: &str) -> String {
+    format!("/{}", key.replace('.', "/"))
+}
+
+fn key<T: Serialize>(value: T) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(key)) => key,
+        _ => String::new(),
+    }
+}
+
+/// The sheet as stored on disk plus a read-only `derived` section holding
+/// everything the rules compute from it.
+fn document(char_sheet: &CharSheet) -> Value {
+    let mut abilities = Map::new();
+    for stat in char_sheet.statistics.ability_scores() {
+        abilities.insert(
+            key(stat.ability),
+            json!({ "score": stat.value, "modifier": stat.modifier }),
+        );
+    }
+    let saving_throws: Map<String, Value> = char_sheet
+        .saving_throw_views()
+        .iter()
+        .map(|st| (key(st.ability), st.value.into()))
+        .collect();
+    let skills: Map<String, Value> = char_sheet
+        .skills_views()
+        .iter()
+        .map(|skill| (key(skill.skill), skill.value.into()))
+        .collect();
+
+    let mut derived = json!({
+        "proficiency_bonus": char_sheet.proficiency_bonus().value,
+        "initiative": char_sheet.initiative().value,
+        "passive_perception": char_sheet.passive_perception().value,
+        "abilities": abilities,
+        "saving_throws": saving_throws,
+        "skills": skills,
+    });
+    derived["speed"] = char_sheet.speed().into();
+    derived["carried_weight"] = char_sheet.inventory.total_weight().into();
+    derived["carrying_capacity"] = char_sheet.carrying_capacity().into();
+    derived["encumbrance"] = char_sheet.encumbrance().label().into();
+    if let (Some(dc), Some(attack)) = (char_sheet.spell_save_dc(), char_sheet.spell_attack_bonus())
+    {
+        derived["spell_save_dc"] = dc.into();
+        derived["spell_attack_bonus"] = attack.into();
+    }
+
+    let mut document = serde_json::to_value(char_sheet).unwrap_or_default();
+    if let Some(object) = document.as_object_mut() {
+        object.insert("derived".into(), derived);
+    }
+    document
+}
+
+fn set(char_sheet: &mut CharSheet, key: &str, raw: &str) -> Result<Value, CommandError> {
+    if key == "schema_version" || key == "derived" || key.starts_with("derived.") {
+        return Err(CommandError::Invalid(format!("'{key}' can't be set")));
+    }
+
+    // Anything that isn't valid JSON is taken as a plain string so names
+    // don't need quoting on the command line.
+    let value: Value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.into()));
+    let pointer = pointer(key);
+    let (parent, field) = pointer.rsplit_once('/').unwrap_or_default();
+
+    let mut document = serde_json::to_value(&*char_sheet).unwrap_or_default();
+    match document.pointer_mut(parent).and_then(Value::as_object_mut) {
+        Some(object) => object.insert(field.to_string(), value.clone()),
+        None => return Err(CommandError::Invalid(format!("no value at '{key}'"))),
+    };
+
+    let mut updated: CharSheet = serde_json::from_value(document)
+        .map_err(|err| CommandError::Invalid(format!("invalid value for '{key}': {err}")))?;
+    updated.clamp_resources();
+
+    // serde ignores fields it doesn't know, so check the value survived the
+    // round trip to catch misspelt keys. A resource's remaining uses can also
+    // have been brought down to its maximum.
+    let stored = serde_json::to_value(&updated).unwrap_or_default();
+    match stored.pointer(&pointer) {
+        Some(stored) if *stored == value => {}
+        None if value.is_null() => {}
+        Some(stored) => {
+            return Err(CommandError::Invalid(format!(
+                "'{key}' can't be {value} on this sheet, at most {stored}"
+            )));
+        }
+        None => return Err(CommandError::Invalid(format!("no value at '{key}'"))),
+    }
+
+    let problems = updated.validate();
+    if !problems.is_empty() {
+        return Err(CommandError::Invalid(problems.join("; ")));
+    }
+
+    *char_sheet = updated;
+    Ok(value)
+}
+
+/// Rolls `check` and adds the roll to the sheet's roll history, each run
+/// being a session of its own.
+fn roll(
+    sheet: &str,
+    check: &str,
+    seed: Option<u64>,
+    advantage: Option<Advantage>,
+    json: bool,
+) -> Result<Output, CommandError> {
+    let char_sheet = load(sheet)?;
+    let target = RollTarget::parse(check)
+        .ok_or_else(|| CommandError::Invalid(format!("unknown check '{check}'")))?;
+    let label = target.label();
+    let modifier = char_sheet.check_modifier(target);
+    let expression = dice::check_expression(modifier, advantage);
+    let roll = dice::roll_expression(&expression, &mut Rng::new(seed), Some(&char_sheet))
+        .map_err(|err| CommandError::Invalid(err.to_string()))?;
+
+    // Not being able to write the history doesn't undo the roll.
+    let session = history::unix_time(SystemTime::now());
+    let entry = HistoryEntry::new(session, &label, &expression, &roll);
+    let warnings = history::append(sheet, &entry)
+        .err()
+        .map(Warning::History)
+        .into_iter()
+        .collect();
+
+    let die = roll.natural_d20().unwrap_or_default();
+    let text = if json {
+        pretty(&json!({
+            "check": label,
+            "die": die,
+            "modifier": modifier,
+            "total": roll.total,
+            "advantage": roll.advantage,
+        }))
+    } else {
+        let label = match roll.advantage {
+            Some(Advantage::Advantage) => format!("{label} with advantage"),
+            Some(Advantage::Disadvantage) => format!("{label} with disadvantage"),
+            None => label,
+        };
+        let note = match die {
+            20 => " natural 20!",
+            1 => " natural 1!",
+            _ => "",
+        };
+        format!("{label}: {} (d20 {die} {modifier:+}){note}", roll.total)
+    };
+    Ok(Output { text, warnings })
+}
+
+fn summary(char_sheet: &CharSheet) -> String {
+    let info = &char_sheet.information;
+    let health = &char_sheet.health;
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "{} - level {} {} {} ({}, {})",
+        info.character_name, info.level, info.race, info.class, info.background, info.alignment
+    );
+    let _ = writeln!(
+        out,
+        "HP {}/{} (temp {})  AC {}  Initiative {:+}  Speed {}",
+        health.current_hp,
+        health.maximum_hp,
+        health.temporary_hp,
+        health.armor_class,
+        char_sheet.initiative().value,
+        char_sheet.speed()
+    );
+    if health.unconscious {
+        let _ = writeln!(
+            out,
+            "{:?} - death saves {} successes, {} failures",
+            health.life_state(),
+            health.death_saves.successes,
+            health.death_saves.failures
+        );
+    }
+    let _ = writeln!(
+        out,
+        "Proficiency {:+}  Passive Perception {}",
+        char_sheet.proficiency_bonus().value,
+        char_sheet.passive_perception().value
+    );
+
+    let _ = writeln!(out, "\nAbilities");
+    let saves = char_sheet.saving_throw_views();
+    for (stat, save) in char_sheet.statistics.ability_scores().iter().zip(saves) {
+        let _ = writeln!(
+            out,
+            "  {} {:>2} ({:+})  save {:+}{}",
+            stat.ability.abbreviation(),
+            stat.value,
+            stat.modifier,
+            save.value,
+            if save.proficient { " *" } else { "" }
+        );
+    }
+
+    let _ = writeln!(out, "\nSkills");
+    for skill in char_sheet.skills_views() {
+        let _ = writeln!(
+            out,
+            "  {} {:<24} {:+}",
+            skill.sp.symbol(),
+            format!(
+                "{} ({})",
+                skill.skill.name(),
+                skill.skill.ability().short_name()
+            ),
+            skill.value
+        );
+    }
+
+    if let (Some(spellcasting), Some(dc), Some(attack)) = (
+        &char_sheet.spellcasting,
+        char_sheet.spell_save_dc(),
+        char_sheet.spell_attack_bonus(),
+    ) {
+        let _ = writeln!(
+            out,
+            "\nSpellcasting ({})  Save DC {dc}  Spell attack {attack:+}",
+            spellcasting.ability.abbreviation()
+        );
+        let slots: Vec<String> = spellcasting
+            .slots
+            .iter()
+            .map(|(level, slots)| {
+                format!(
+                    "{} {}/{}",
+                    spells::level_name(*level),
+                    slots.available(),
+                    slots.max
+                )
+            })
+            .collect();
+        if !slots.is_empty() {
+            let _ = writeln!(out, "  Slots: {}", slots.join("  "));
+        }
+        if let Some(pact) = spellcasting.pact_magic {
+            let _ = writeln!(
+                out,
+                "  Pact slots ({}): {}/{}",
+                spells::level_name(pact.level),
+                pact.slots.available(),
+                pact.slots.max
+            );
+        }
+        for spell in &spellcasting.spells {
+            let _ = writeln!(
+                out,
+                "  {} {:<24} {}",
+                if spellcasting.castable(spell) {
+                    "*"
+                } else {
+                    " "
+                },
+                spell.name,
+                spells::level_name(spell.level)
+            );
+        }
+    }
+
+    let inventory = &char_sheet.inventory;
+    if !inventory.items.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nInventory  {} / {} lb  {}",
+            inventory::format_weight(inventory.total_weight()),
+            inventory::format_weight(char_sheet.carrying_capacity()),
+            char_sheet.encumbrance().label()
+        );
+        for (path, item) in inventory.rows() {
+            let name = format!("{}{}", "  ".repeat(path.len() - 1), item.name);
+            let _ = writeln!(
+                out,
+                "  {} {name:<28} x{:<4} {} lb",
+                if item.equipped { "*" } else { " " },
+                item.quantity,
+                inventory::format_weight(item.total_weight())
+            );
+        }
+    }
+
+    let wallet = &char_sheet.wallet;
+    if !wallet.is_empty() {
+        let coins: Vec<String> = Coin::ALL
+            .into_iter()
+            .map(|coin| format!("{} {}", wallet.coins(coin), coin.abbreviation()))
+            .collect();
+        let _ = writeln!(
+            out,
+            "\nWallet  {}  (worth {})",
+            coins.join("  "),
+            wallet::format_amount(wallet.total_cp())
+        );
+    }
+
+    if !char_sheet.resources.is_empty() {
+        let _ = writeln!(out, "\nResources");
+        for resource in &char_sheet.resources {
+            let _ = writeln!(
+                out,
+                "  {:<24} {}/{}  ({})",
+                resource.name,
+                resource.current,
+                resource.max(char_sheet),
+                resource.recharge.label()
+            );
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Object(_) | Value::Array(_) => pretty(value),
+        other => other.to_string(),
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use char_sheet::history;
+    use char_sheet::resource::{Recharge, Resource};
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A sheet saved in a directory of its own, removed when the test is
+    /// done with it.
+    struct TempSheet(PathBuf);
+
+    impl TempSheet {
+        fn new(name: &str) -> TempSheet {
+            let dir = std::env::temp_dir()
+                .join(format!("char-sheet-commands-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+
+            let mut sheet = CharSheet::blank();
+            sheet.information.character_name = "Tess".into();
+            let path = dir.join("sheet.json");
+            storage::save_sheet(&path.to_string_lossy(), &sheet, 0).unwrap();
+            TempSheet(dir)
+        }
+
+        fn path(&self) -> String {
+            self.0.join("sheet.json").to_string_lossy().into_owned()
+        }
+
+        fn contents(&self) -> String {
+            fs::read_to_string(self.path()).unwrap()
+        }
+    }
+
+    impl Drop for TempSheet {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// What `command` prints on stdout.
+    fn output(command: Headless, json: bool) -> Result<String, CommandError> {
+        execute(command, json).map(|output| output.text)
+    }
+
+    fn set_value(sheet: &TempSheet, key: &str, value: &str) -> Result<String, CommandError> {
+        output(
+            Headless::Set {
+                sheet: sheet.path(),
+                key: key.into(),
+                value: value.into(),
+                backups: 1,
+            },
+            false,
+        )
+    }
+
+    #[test]
+    fn set_saves_a_valid_value() {
+        let sheet = TempSheet::new("set");
+        assert_eq!(
+            set_value(&sheet, "statistics.strength", "16")
+                .ok()
+                .as_deref(),
+            Some("statistics.strength = 16")
+        );
+        assert_eq!(
+            set_value(&sheet, "information.class", "Fighter")
+                .ok()
+                .as_deref(),
+            Some("information.class = Fighter")
+        );
+
+        let saved = storage::load_sheet(&sheet.path()).unwrap();
+        assert_eq!(saved.statistics.strength, 16);
+        assert_eq!(saved.information.class, "Fighter");
+        // Only as many backups as were asked for are kept.
+        assert_eq!(storage::list_backups(&sheet.path()).len(), 1);
+    }
+
+    #[test]
+    fn set_leaves_the_file_alone_when_the_value_is_refused() {
+        let sheet = TempSheet::new("set-invalid");
+        let before = sheet.contents();
+
+        for (key, value) in [
+            ("statistics.strength", "99"),
+            ("statistics.strength", "strong"),
+            ("statistics.strenght", "12"),
+            ("derived.initiative", "3"),
+        ] {
+            let err = set_value(&sheet, key, value).expect_err(key);
+            assert_eq!(err.exit_code(), EXIT_INVALID, "{key} = {value}");
+        }
+        assert_eq!(sheet.contents(), before);
+    }
+
+    #[test]
+    fn set_brings_resources_within_a_lowered_maximum() {
+        let sheet = TempSheet::new("set-resource");
+        let mut char_sheet = storage::load_sheet(&sheet.path()).unwrap();
+        char_sheet.statistics.charisma = 16;
+        char_sheet.resources.push(Resource {
+            name: "Bardic Inspiration".into(),
+            max: 1,
+            current: 3,
+            recharge: Recharge::LongRest,
+            max_formula: Some("@cha".into()),
+        });
+        storage::save_sheet(&sheet.path(), &char_sheet, 0).unwrap();
+
+        assert!(set_value(&sheet, "statistics.charisma", "12").is_ok());
+        let saved = storage::load_sheet(&sheet.path()).unwrap();
+        assert_eq!(saved.resources[0].current, 1);
+
+        let err = set_value(&sheet, "resources.0.current", "5").expect_err("above the maximum");
+        assert_eq!(
+            err.to_string(),
+            "'resources.0.current' can't be 5 on this sheet, at most 1"
+        );
+    }
+
+    #[test]
+    fn show_and_get_read_the_sheet() {
+        let sheet = TempSheet::new("show");
+        let summary = output(
+            Headless::Show {
+                sheet: sheet.path(),
+            },
+            false,
+        )
+        .ok()
+        .unwrap();
+        assert!(summary.starts_with("Tess - level 1"), "{summary}");
+
+        let get = |key: &str| {
+            output(
+                Headless::Get {
+                    sheet: sheet.path(),
+                    key: key.into(),
+                },
+                false,
+            )
+            .ok()
+        };
+        assert_eq!(get("derived.proficiency_bonus").as_deref(), Some("2"));
+        assert_eq!(get("information.character_name").as_deref(), Some("Tess"));
+        assert_eq!(get("information.nickname"), None);
+    }
+
+    #[test]
+    fn roll_is_recorded_in_the_history() {
+        let sheet = TempSheet::new("roll");
+        let roll = |advantage| {
+            output(
+                Headless::Roll {
+                    sheet: sheet.path(),
+                    check: "athletics".into(),
+                    seed: Some(7),
+                    advantage,
+                },
+                true,
+            )
+            .ok()
+            .unwrap()
+        };
+        let plain: serde_json::Value = serde_json::from_str(&roll(None)).unwrap();
+        let with_advantage: serde_json::Value =
+            serde_json::from_str(&roll(Some(Advantage::Advantage))).unwrap();
+        assert_eq!(plain["check"], "Athletics");
+        assert_eq!(with_advantage["advantage"], "advantage");
+
+        let entries = history::load(&sheet.path()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].label, "Athletics");
+        assert_eq!(entries[0].total, plain["total"]);
+        assert_eq!(entries[1].advantage, Some(Advantage::Advantage));
+        assert_eq!(entries[1].dice.len(), 2);
+    }
+
+    #[test]
+    fn roll_warns_when_the_history_cant_be_written() {
+        let sheet = TempSheet::new("roll-warning");
+        // A directory where the history file should go.
+        fs::create_dir(history::history_path(std::path::Path::new(&sheet.path()))).unwrap();
+
+        let output = execute(
+            Headless::Roll {
+                sheet: sheet.path(),
+                check: "str".into(),
+                seed: Some(3),
+                advantage: None,
+            },
+            false,
+        )
+        .ok()
+        .unwrap();
+        assert!(output.text.starts_with("STR check: "), "{}", output.text);
+        assert!(matches!(output.warnings[..], [Warning::History(_)]));
+    }
+}
@@ -0,0 +1,777 @@
+// Dice expressions such as `2d6+3`, `1d20+@str`, `4d6kh3`, `1d20adv` and
+// `8d6/2`. An expression is parsed once into an `Expression` and can then be
+// rolled any number of times against any `DieRoller`.
+
+use std::error::Error;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::sheet::{CharSheet, RollTarget};
+
+// Keeps a typo like `1000d1000` from locking up the app.
+const MAX_DICE: u32 = 100;
+const MAX_SIDES: u32 = 1000;
+// Keeps something like `((((…` or `1+1+1+…` from running the parser, or the
+// roll, out of stack.
+const MAX_NESTING: usize = 64;
+const MAX_OPERATORS: usize = 256;
+
+/// Anything that can roll a die. The app uses `Rng`, seeded from the clock
+/// or from `--seed`; tests can use a fixed sequence.
+pub trait DieRoller {
+    /// Rolls a single die with `sides` faces, returning 1..=sides.
+    fn roll(&mut self, sides: u32) -> u32;
+}
+
+impl<R: DieRoller + ?Sized> DieRoller for Box<R> {
+    fn roll(&mut self, sides: u32) -> u32 {
+        (**self).roll(sides)
+    }
+}
+
+/// A small SplitMix64 generator. Dice don't need cryptographic quality and
+/// this keeps the crate free of extra dependencies.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// The same seed always gives the same sequence of rolls.
+    pub fn seeded(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// `Rng::seeded` when a seed was given, `Rng::from_time` otherwise.
+    pub fn new(seed: Option<u64>) -> Rng {
+        seed.map_or_else(Rng::from_time, Rng::seeded)
+    }
+
+    /// Seeds from the system clock.
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        Rng::seeded(nanos)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl DieRoller for Rng {
+    fn roll(&mut self, sides: u32) -> u32 {
+        // Rejection sampling keeps every face equally likely.
+        let sides = u64::from(sides.max(1));
+        let zone = u64::MAX - u64::MAX % sides;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % sides) as u32 + 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiceError(pub String);
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for DiceError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Advantage {
+    Advantage,
+    Disadvantage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    Number(i32),
+    Dice {
+        count: u32,
+        sides: u32,
+        keep: Option<Keep>,
+    },
+    Reference(String),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+}
+
+/// One die that was rolled. Dice dropped by `kh`/`kl` or advantage are kept
+/// in the roll so they can be shown, but don't count towards the total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DieResult {
+    pub sides: u32,
+    pub value: u32,
+    pub kept: bool,
+}
+
+/// The outcome of rolling an expression.
+#[derive(Clone, Debug)]
+pub struct Roll {
+    pub dice: Vec<DieResult>,
+    pub total: i32,
+    pub advantage: Option<Advantage>,
+    /// The expression with every die and reference filled in, e.g.
+    /// `[4, 2] + 3`.
+    pub breakdown: String,
+}
+
+impl Roll {
+    /// The face of the d20 that counted, when the roll was a single d20
+    /// check (with or without advantage). Used to spot natural 1s and 20s.
+    pub fn natural_d20(&self) -> Option<u32> {
+        natural_d20(&self.dice)
+    }
+}
+
+/// See `Roll::natural_d20`. Only ever a face from 1 to 20, whatever a
+/// history file claims was rolled.
+pub fn natural_d20(dice: &[DieResult]) -> Option<u32> {
+    let mut kept = dice.iter().filter(|die| die.kept);
+    match (kept.next(), kept.next()) {
+        (Some(die), None) if die.sides == 20 && (1..=20).contains(&die.value) => Some(die.value),
+        _ => None,
+    }
+}
+
+/// A parsed dice expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expression {
+    root: Node,
+    advantage: Option<Advantage>,
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Expression, DiceError> {
+        let mut parser = Parser {
+            chars: text.chars().filter(|c| !c.is_whitespace()).collect(),
+            pos: 0,
+            depth: 0,
+            operators: 0,
+            advantage: None,
+        };
+        if parser.chars.is_empty() {
+            return Err(DiceError("enter a number or dice such as 2d6+3".into()));
+        }
+
+        let root = parser.expression()?;
+        if let Some(c) = parser.peek() {
+            return Err(parser.unexpected(c));
+        }
+        Ok(Expression {
+            root,
+            advantage: parser.advantage,
+        })
+    }
+
+    /// Rolls the expression. `@` references are looked up on `sheet` and
+    /// are an error when there is no sheet.
+    pub fn roll(
+        &self,
+        roller: &mut impl DieRoller,
+        sheet: Option<&CharSheet>,
+    ) -> Result<Roll, DiceError> {
+        let mut roll = Roll {
+            dice: Vec::new(),
+            total: 0,
+            advantage: self.advantage,
+            breakdown: String::new(),
+        };
+        let mut evaluator = Evaluator {
+            roller,
+            sheet,
+            dice: &mut roll.dice,
+        };
+        let (total, breakdown) = evaluator.evaluate(&self.root)?;
+        roll.total = total;
+        roll.breakdown = breakdown;
+        Ok(roll)
+    }
+
+    /// Whether rolling the expression rolls any dice at all.
+    pub fn has_dice(&self) -> bool {
+        fn walk(node: &Node) -> bool {
+            match node {
+                Node::Dice { .. } => true,
+                Node::Number(_) | Node::Reference(_) => false,
+                Node::Negate(inner) => walk(inner),
+                Node::Binary(_, left, right) => walk(left) || walk(right),
+            }
+        }
+        walk(&self.root)
+    }
+}
+
+/// Works out a formula such as `@cha` or `@level/2+1` against `sheet`.
+/// Formulas are for numbers derived from the sheet, so dice are an error.
+pub fn evaluate(formula: &str, sheet: &CharSheet) -> Result<i32, DiceError> {
+    let expression = Expression::parse(formula)?;
+    if expression.has_dice() {
+        return Err(DiceError("a formula can't roll dice".into()));
+    }
+    Ok(expression.roll(&mut NoDice, Some(sheet))?.total)
+}
+
+/// The roller behind `evaluate`, which has already made sure no die is rolled.
+struct NoDice;
+
+impl DieRoller for NoDice {
+    fn roll(&mut self, _sides: u32) -> u32 {
+        unreachable!("formulas are checked for dice before they are evaluated")
+    }
+}
+
+/// Parses and rolls `expression` in one go.
+pub fn roll_expression(
+    expression: &str,
+    roller: &mut impl DieRoller,
+    sheet: Option<&CharSheet>,
+) -> Result<Roll, DiceError> {
+    Expression::parse(expression)?.roll(roller, sheet)
+}
+
+/// The expression for a d20 check with `modifier`, e.g. `1d20adv+5`.
+pub fn check_expression(modifier: i8, advantage: Option<Advantage>) -> String {
+    let die = match advantage {
+        None => "1d20",
+        Some(Advantage::Advantage) => "1d20adv",
+        Some(Advantage::Disadvantage) => "1d20dis",
+    };
+    format!("{die}{modifier:+}")
+}
+
+/// Looks up the number an `@` reference stands for: an ability modifier
+/// (`@str`, `@dexterity`), `@prof`, `@level`, a skill total (`@stealth`,
+/// `@sleight_of_hand`), a saving throw (`@wis_save`) or `@initiative`.
+pub fn resolve_reference(name: &str, sheet: &CharSheet) -> Option<i32> {
+    if name.eq_ignore_ascii_case("prof") || name.eq_ignore_ascii_case("proficiency") {
+        return Some(i32::from(sheet.proficiency_bonus().value));
+    }
+    if name.eq_ignore_ascii_case("level") {
+        return Some(i32::from(sheet.information.level));
+    }
+    RollTarget::parse(name).map(|target| i32::from(sheet.check_modifier(target)))
+}
+
+// expression := term (('+' | '-') term)*
+// term       := factor (('*' | '/') factor)*
+// factor     := '-' factor | '(' expression ')' | '@' name | dice | number
+// dice       := number? 'd' number ('kh' number | 'kl' number | 'adv' | 'dis')?
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    // Brackets and negations currently open.
+    depth: usize,
+    // Every operator adds a level to the tree, like a bracket does.
+    operators: usize,
+    advantage: Option<Advantage>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self
+            .peek()
+            .is_some_and(|next| next.eq_ignore_ascii_case(&c))
+        {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let end = self.pos + word.len();
+        let matches = end <= self.chars.len()
+            && self.chars[self.pos..end]
+                .iter()
+                .zip(word.chars())
+                .all(|(a, b)| a.eq_ignore_ascii_case(&b));
+        if matches {
+            self.pos = end;
+        }
+        matches
+    }
+
+    fn unexpected(&self, c: char) -> DiceError {
+        DiceError(format!("unexpected '{c}' at position {}", self.pos + 1))
+    }
+
+    fn expression(&mut self) -> Result<Node, DiceError> {
+        let mut node = self.term()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(node);
+            };
+            self.count_operator()?;
+            node = Node::Binary(operator, Box::new(node), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Node, DiceError> {
+        let mut node = self.factor()?;
+        loop {
+            let operator = if self.eat('*') {
+                Operator::Multiply
+            } else if self.eat('/') {
+                Operator::Divide
+            } else {
+                return Ok(node);
+            };
+            self.count_operator()?;
+            node = Node::Binary(operator, Box::new(node), Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Node, DiceError> {
+        match self.peek() {
+            None => Err(DiceError("the expression ends too early".into())),
+            Some('-') => {
+                self.pos += 1;
+                let node = self.nested(Parser::factor)?;
+                Ok(Node::Negate(Box::new(node)))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let node = self.nested(Parser::expression)?;
+                if !self.eat(')') {
+                    return Err(match self.peek() {
+                        Some(c) => self.unexpected(c),
+                        None => DiceError("missing ')'".into()),
+                    });
+                }
+                Ok(node)
+            }
+            Some('@') => {
+                self.pos += 1;
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(DiceError(format!(
+                        "'@' at position {start} needs a name such as @str"
+                    )));
+                }
+                Ok(Node::Reference(
+                    self.chars[start..self.pos].iter().collect(),
+                ))
+            }
+            Some(c) if c.is_ascii_digit() || c.eq_ignore_ascii_case(&'d') => self.dice_or_number(),
+            Some(c) => Err(self.unexpected(c)),
+        }
+    }
+
+    fn count_operator(&mut self) -> Result<(), DiceError> {
+        self.operators += 1;
+        if self.operators > MAX_OPERATORS {
+            return Err(DiceError(format!(
+                "an expression can have at most {MAX_OPERATORS} operators"
+            )));
+        }
+        Ok(())
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Parser) -> Result<Node, DiceError>,
+    ) -> Result<Node, DiceError> {
+        if self.depth == MAX_NESTING {
+            return Err(DiceError(format!(
+                "brackets and minus signs can only nest {MAX_NESTING} deep"
+            )));
+        }
+        self.depth += 1;
+        let node = parse(self);
+        self.depth -= 1;
+        node
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok()
+    }
+
+    fn dice_or_number(&mut self) -> Result<Node, DiceError> {
+        let count = self.number();
+        if !self.eat('d') {
+            return match count {
+                Some(number) => i32::try_from(number)
+                    .map(Node::Number)
+                    .map_err(|_| DiceError(format!("{number} is too large"))),
+                None => Err(DiceError("expected a number".into())),
+            };
+        }
+
+        let mut count = count.unwrap_or(1);
+        let sides = self.number().ok_or_else(|| {
+            DiceError(format!(
+                "expected the number of sides after 'd' at position {}",
+                self.pos
+            ))
+        })?;
+        if count == 0 || count > MAX_DICE {
+            return Err(DiceError(format!(
+                "can roll between 1 and {MAX_DICE} dice at once"
+            )));
+        }
+        if sides == 0 || sides > MAX_SIDES {
+            return Err(DiceError(format!(
+                "dice need between 1 and {MAX_SIDES} sides"
+            )));
+        }
+
+        let keep = if self.eat_word("kh") {
+            Some(Keep::Highest(self.keep_count(count)?))
+        } else if self.eat_word("kl") {
+            Some(Keep::Lowest(self.keep_count(count)?))
+        } else if self.eat_word("adv") {
+            self.advantage_on(count, Advantage::Advantage)?;
+            count = 2;
+            Some(Keep::Highest(1))
+        } else if self.eat_word("dis") {
+            self.advantage_on(count, Advantage::Disadvantage)?;
+            count = 2;
+            Some(Keep::Lowest(1))
+        } else {
+            None
+        };
+
+        Ok(Node::Dice { count, sides, keep })
+    }
+
+    fn keep_count(&mut self, count: u32) -> Result<u32, DiceError> {
+        match self.number() {
+            Some(keep) if (1..=count).contains(&keep) => Ok(keep),
+            _ => Err(DiceError(format!(
+                "can only keep between 1 and {count} of {count} dice"
+            ))),
+        }
+    }
+
+    fn advantage_on(&mut self, count: u32, advantage: Advantage) -> Result<(), DiceError> {
+        if count != 1 {
+            return Err(DiceError(
+                "advantage and disadvantage apply to a single die such as 1d20adv".into(),
+            ));
+        }
+        if self.advantage.is_some_and(|existing| existing != advantage) {
+            return Err(DiceError(
+                "a roll can't have both advantage and disadvantage".into(),
+            ));
+        }
+        self.advantage = Some(advantage);
+        Ok(())
+    }
+}
+
+struct Evaluator<'a, R: DieRoller> {
+    roller: &'a mut R,
+    sheet: Option<&'a CharSheet>,
+    dice: &'a mut Vec<DieResult>,
+}
+
+impl<R: DieRoller> Evaluator<'_, R> {
+    /// Returns the value of `node` and how it is written in the breakdown.
+    fn evaluate(&mut self, node: &Node) -> Result<(i32, String), DiceError> {
+        match node {
+            Node::Number(number) => Ok((*number, number.to_string())),
+            Node::Reference(name) => {
+                let sheet = self.sheet.ok_or_else(|| {
+                    DiceError(format!("@{name} needs a character sheet to look it up"))
+                })?;
+                let value = resolve_reference(name, sheet)
+                    .ok_or_else(|| DiceError(format!("unknown reference @{name}")))?;
+                Ok((value, value.to_string()))
+            }
+            Node::Negate(inner) => {
+                let (value, text) = self.evaluate(inner)?;
+                Ok((value.saturating_neg(), format!("-{text}")))
+            }
+            Node::Dice { count, sides, keep } => Ok(self.roll_dice(*count, *sides, *keep)),
+            Node::Binary(operator, left, right) => {
+                let (left, left_text) = self.evaluate(left)?;
+                let (right, right_text) = self.evaluate(right)?;
+                let (value, symbol) = match operator {
+                    Operator::Add => (left.saturating_add(right), "+"),
+                    Operator::Subtract => (left.saturating_sub(right), "-"),
+                    Operator::Multiply => (left.saturating_mul(right), "*"),
+                    Operator::Divide if right == 0 => {
+                        return Err(DiceError("can't divide by zero".into()));
+                    }
+                    // Halving and the like always round down.
+                    Operator::Divide => (left.div_euclid(right), "/"),
+                };
+                Ok((value, format!("{left_text} {symbol} {right_text}")))
+            }
+        }
+    }
+
+    fn roll_dice(&mut self, count: u32, sides: u32, keep: Option<Keep>) -> (i32, String) {
+        let values: Vec<u32> = (0..count).map(|_| self.roller.roll(sides)).collect();
+
+        // Work out which dice count, dropping from the other end first.
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by_key(|&i| values[i]);
+        let mut kept = vec![true; values.len()];
+        let dropped = match keep {
+            Some(Keep::Highest(n)) => &order[..values.len() - n as usize],
+            Some(Keep::Lowest(n)) => &order[n as usize..],
+            None => &[][..],
+        };
+        for &i in dropped {
+            kept[i] = false;
+        }
+
+        let mut total = 0i32;
+        let mut shown = Vec::with_capacity(values.len());
+        for (value, kept) in values.iter().zip(kept) {
+            self.dice.push(DieResult {
+                sides,
+                value: *value,
+                kept,
+            });
+            if kept {
+                total = total.saturating_add(*value as i32);
+                shown.push(value.to_string());
+            } else {
+                shown.push(format!("({value})"));
+            }
+        }
+        (total, format!("[{}]", shown.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sheet::Skill;
+
+    /// Hands out the given faces in order.
+    struct Fixed(Vec<u32>);
+
+    impl DieRoller for Fixed {
+        fn roll(&mut self, sides: u32) -> u32 {
+            let value = self.0.remove(0);
+            assert!(
+                (1..=sides).contains(&value),
+                "{value} is not a face of a d{sides}"
+            );
+            value
+        }
+    }
+
+    fn sheet() -> CharSheet {
+        let mut sheet = CharSheet::blank();
+        sheet.information.level = 5; // proficiency +3
+        sheet.statistics.strength = 16; // +3
+        sheet.statistics.dexterity = 8; // -1
+        sheet.skills.cycle(Skill::Stealth); // proficient: -1 + 3
+        sheet
+    }
+
+    fn roll(text: &str, faces: &[u32]) -> Roll {
+        roll_expression(text, &mut Fixed(faces.to_vec()), Some(&sheet())).unwrap()
+    }
+
+    fn error(text: &str) -> String {
+        roll_expression(text, &mut Fixed(vec![1; 10]), Some(&sheet()))
+            .unwrap_err()
+            .0
+    }
+
+    #[test]
+    fn adds_dice_and_modifiers() {
+        let roll = roll("2d6+3", &[4, 2]);
+        assert_eq!(roll.total, 9);
+        assert_eq!(roll.breakdown, "[4, 2] + 3");
+        assert_eq!(roll.dice.len(), 2);
+        assert!(roll.dice.iter().all(|die| die.kept && die.sides == 6));
+    }
+
+    #[test]
+    fn plain_numbers_and_precedence() {
+        assert_eq!(roll("23", &[]).total, 23);
+        assert_eq!(roll("2+3*4", &[]).total, 14);
+        assert_eq!(roll("(2+3)*4", &[]).total, 20);
+        assert_eq!(roll("-d4+1", &[3]).total, -2);
+        assert_eq!(roll(" 1 d 8 + 2 ", &[5]).total, 7);
+    }
+
+    #[test]
+    fn resolves_sheet_references() {
+        assert_eq!(roll("1d20+@str", &[10]).total, 13);
+        assert_eq!(roll("1d20+@dexterity", &[10]).total, 9);
+        assert_eq!(roll("@prof", &[]).total, 3);
+        assert_eq!(roll("1d20+@stealth", &[10]).total, 12);
+        assert_eq!(roll("1d20+@STR_save", &[10]).total, 13);
+        assert_eq!(roll("1d20+@str", &[10]).breakdown, "[10] + 3");
+    }
+
+    #[test]
+    fn keeps_highest_and_lowest() {
+        let highest = roll("4d6kh3", &[3, 6, 1, 5]);
+        assert_eq!(highest.total, 14);
+        assert_eq!(highest.breakdown, "[3, 6, (1), 5]");
+        assert_eq!(highest.dice.iter().filter(|die| !die.kept).count(), 1);
+
+        assert_eq!(roll("3d6kl1", &[4, 2, 6]).total, 2);
+    }
+
+    #[test]
+    fn advantage_and_disadvantage() {
+        let advantage = roll("1d20adv+2", &[7, 15]);
+        assert_eq!(advantage.total, 17);
+        assert_eq!(advantage.advantage, Some(Advantage::Advantage));
+        assert_eq!(advantage.natural_d20(), Some(15));
+
+        let disadvantage = roll("d20dis", &[7, 15]);
+        assert_eq!(disadvantage.total, 7);
+        assert_eq!(disadvantage.advantage, Some(Advantage::Disadvantage));
+
+        assert_eq!(roll("2d20", &[1, 2]).natural_d20(), None);
+        assert_eq!(roll("1d20+5", &[20]).natural_d20(), Some(20));
+    }
+
+    #[test]
+    fn check_expressions_round_trip() {
+        assert_eq!(check_expression(5, None), "1d20+5");
+        assert_eq!(
+            check_expression(-1, Some(Advantage::Disadvantage)),
+            "1d20dis-1"
+        );
+        let roll = roll(&check_expression(0, Some(Advantage::Advantage)), &[3, 18]);
+        assert_eq!(roll.total, 18);
+        assert_eq!(roll.natural_d20(), Some(18));
+    }
+
+    #[test]
+    fn division_rounds_down() {
+        assert_eq!(roll("8d6/2", &[1, 2, 3, 4, 5, 6, 1, 1]).total, 11);
+        assert_eq!(roll("7/2", &[]).total, 3);
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        assert_eq!(error(""), "enter a number or dice such as 2d6+3");
+        assert_eq!(error("2d6+"), "the expression ends too early");
+        assert_eq!(error("2x6"), "unexpected 'x' at position 2");
+        assert_eq!(error("(1d6"), "missing ')'");
+        assert_eq!(error("1d20+@luck"), "unknown reference @luck");
+        assert_eq!(error("4d6kh5"), "can only keep between 1 and 4 of 4 dice");
+        assert_eq!(
+            error("2d20adv"),
+            "advantage and disadvantage apply to a single die such as 1d20adv"
+        );
+        assert_eq!(
+            error("1d20adv+1d20dis"),
+            "a roll can't have both advantage and disadvantage"
+        );
+        assert_eq!(error("1d6/0"), "can't divide by zero");
+        assert_eq!(error("0d6"), "can roll between 1 and 100 dice at once");
+        assert_eq!(error("1d0"), "dice need between 1 and 1000 sides");
+    }
+
+    #[test]
+    fn limits_how_deeply_an_expression_nests() {
+        let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(roll(&nested(64), &[]).total, 1);
+        assert_eq!(roll(&format!("{}1", "-".repeat(64)), &[]).total, 1);
+
+        let too_deep = "brackets and minus signs can only nest 64 deep";
+        assert_eq!(error(&nested(65)), too_deep);
+        assert_eq!(error(&format!("{}1", "-".repeat(100_000))), too_deep);
+        assert_eq!(error(&"(".repeat(100_000)), too_deep);
+        assert!(evaluate(&format!("{}1", "-(".repeat(50_000)), &sheet()).is_err());
+
+        let chain = |operators| format!("1{}", "+1".repeat(operators));
+        assert_eq!(roll(&chain(256), &[]).total, 257);
+        assert_eq!(
+            error(&chain(200_000)),
+            "an expression can have at most 256 operators"
+        );
+    }
+
+    #[test]
+    fn evaluates_formulas_without_dice() {
+        let sheet = sheet();
+        assert_eq!(evaluate("@level", &sheet), Ok(5));
+        assert_eq!(evaluate("@level/2+@str", &sheet), Ok(5));
+        assert_eq!(evaluate("@prof", &sheet), Ok(3));
+        assert_eq!(
+            evaluate("1d4+@str", &sheet).unwrap_err().0,
+            "a formula can't roll dice"
+        );
+    }
+
+    #[test]
+    fn references_need_a_sheet() {
+        let err = roll_expression("1d20+@str", &mut Fixed(vec![5]), None).unwrap_err();
+        assert_eq!(err.0, "@str needs a character sheet to look it up");
+    }
+
+    #[test]
+    fn seeded_rng_is_repeatable_and_in_range() {
+        let expression = Expression::parse("10d20+@prof").unwrap();
+        let first = expression
+            .roll(&mut Rng::seeded(42), Some(&sheet()))
+            .unwrap();
+        let second = expression
+            .roll(&mut Rng::seeded(42), Some(&sheet()))
+            .unwrap();
+        assert_eq!(first.dice, second.dice);
+        assert_eq!(first.total, second.total);
+        assert!(first.dice.iter().all(|die| (1..=20).contains(&die.value)));
+
+        let mut rng = Rng::seeded(7);
+        let mut seen = [false; 6];
+        for _ in 0..200 {
+            seen[rng.roll(6) as usize - 1] = true;
+        }
+        assert!(seen.iter().all(|face| *face), "every face of a d6 turns up");
+    }
+}
@@ -0,0 +1,264 @@
+// The roll history kept next to a sheet. Every roll is appended to
+// `<sheet>.rolls.jsonl` as one JSON object per line, so the sheet itself
+// doesn't grow and an interrupted write can only ever lose the last roll.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dice::{self, Advantage, DieResult, Roll};
+
+/// One roll as it is stored in the history file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    /// When the session the roll was made in started, also in seconds since
+    /// the epoch. Rolls made in the same run of the app share a session.
+    pub session: u64,
+    pub label: String,
+    pub expression: String,
+    pub dice: Vec<DieResult>,
+    pub total: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advantage: Option<Advantage>,
+}
+
+impl HistoryEntry {
+    pub fn new(session: u64, label: &str, expression: &str, roll: &Roll) -> HistoryEntry {
+        HistoryEntry {
+            time: unix_time(SystemTime::now()),
+            session,
+            label: label.to_string(),
+            expression: expression.to_string(),
+            dice: roll.dice.clone(),
+            total: roll.total,
+            advantage: roll.advantage,
+        }
+    }
+}
+
+pub fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// `dir/name.json` -> `dir/name.rolls.jsonl`
+pub fn history_path(sheet: &Path) -> PathBuf {
+    sheet.with_extension("rolls.jsonl")
+}
+
+/// Appends `entry` to the history kept for the sheet at `sheet`.
+pub fn append(sheet: &str, entry: &HistoryEntry) -> io::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path(Path::new(sheet)))?;
+    file.write_all(line.as_bytes())
+}
+
+/// Reads the history kept for the sheet at `sheet`, oldest roll first. A
+/// sheet that was never rolled for has an empty history. Lines that can't be
+/// read, such as one cut short by a crash, are skipped.
+pub fn load(sheet: &str) -> io::Result<Vec<HistoryEntry>> {
+    let contents = match fs::read_to_string(history_path(Path::new(sheet))) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// How the dice fell over a number of rolls. Only single d20 rolls (checks,
+/// saves, attacks and death saves, with or without advantage) go into the
+/// d20 figures; the face that counted is the one recorded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RollStats {
+    pub rolls: u32,
+    /// How often each face came up, index 0 being a natural 1.
+    pub distribution: [u32; 20],
+}
+
+impl RollStats {
+    pub fn add(&mut self, entry: &HistoryEntry) {
+        self.rolls += 1;
+        if let Some(face) = dice::natural_d20(&entry.dice) {
+            self.distribution[face as usize - 1] += 1;
+        }
+    }
+
+    pub fn combine(&mut self, other: &RollStats) {
+        self.rolls += other.rolls;
+        for (count, more) in self.distribution.iter_mut().zip(other.distribution) {
+            *count += more;
+        }
+    }
+
+    pub fn d20_rolls(&self) -> u32 {
+        self.distribution.iter().sum()
+    }
+
+    /// The average natural d20, `None` before the first one. A fair die
+    /// averages 10.5.
+    pub fn average_d20(&self) -> Option<f64> {
+        let faces: u32 = (1..=20).zip(self.distribution).map(|(f, n)| f * n).sum();
+        match self.d20_rolls() {
+            0 => None,
+            count => Some(f64::from(faces) / f64::from(count)),
+        }
+    }
+
+    pub fn natural_20s(&self) -> u32 {
+        self.distribution[19]
+    }
+
+    pub fn natural_1s(&self) -> u32 {
+        self.distribution[0]
+    }
+}
+
+pub struct SessionStats {
+    pub session: u64,
+    pub stats: RollStats,
+}
+
+/// Groups `entries` by session, newest session first.
+pub fn session_stats(entries: &[HistoryEntry]) -> Vec<SessionStats> {
+    let mut sessions: Vec<SessionStats> = Vec::new();
+    for entry in entries {
+        let index = match sessions.iter().position(|s| s.session == entry.session) {
+            Some(index) => index,
+            None => {
+                sessions.push(SessionStats {
+                    session: entry.session,
+                    stats: RollStats::default(),
+                });
+                sessions.len() - 1
+            }
+        };
+        sessions[index].stats.add(entry);
+    }
+
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.session));
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d20(session: u64, face: u32) -> HistoryEntry {
+        HistoryEntry {
+            time: session,
+            session,
+            label: "Athletics".into(),
+            expression: "1d20+5".into(),
+            dice: vec![DieResult {
+                sides: 20,
+                value: face,
+                kept: true,
+            }],
+            total: face as i32 + 5,
+            advantage: None,
+        }
+    }
+
+    #[test]
+    fn appended_rolls_read_back() {
+        let dir = std::env::temp_dir().join(format!("char-sheet-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let sheet = dir.join("hero.json").to_string_lossy().into_owned();
+
+        assert!(load(&sheet).unwrap().is_empty());
+        let mut first = d20(1, 12);
+        first.advantage = Some(Advantage::Advantage);
+        first.dice.insert(
+            0,
+            DieResult {
+                sides: 20,
+                value: 3,
+                kept: false,
+            },
+        );
+        append(&sheet, &first).unwrap();
+        append(&sheet, &d20(1, 20)).unwrap();
+        // A line cut short by a crash is skipped, not fatal.
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(history_path(Path::new(&sheet)))
+            .unwrap();
+        file.write_all(b"{\"time\":1,\"sess").unwrap();
+
+        let entries = load(&sheet);
+        let _ = fs::remove_dir_all(&dir);
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].advantage, Some(Advantage::Advantage));
+        assert_eq!(entries[0].dice.len(), 2);
+        assert_eq!(dice::natural_d20(&entries[0].dice), Some(12));
+        assert_eq!(entries[1].total, 25);
+    }
+
+    #[test]
+    fn impossible_faces_stay_out_of_the_statistics() {
+        let dir = std::env::temp_dir().join(format!("char-sheet-faces-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let sheet = dir.join("hero.json").to_string_lossy().into_owned();
+        for face in [0, 21, 7] {
+            append(&sheet, &d20(1, face)).unwrap();
+        }
+
+        let entries = load(&sheet);
+        let _ = fs::remove_dir_all(&dir);
+        let sessions = session_stats(&entries.unwrap());
+        let stats = sessions[0].stats;
+        assert_eq!((stats.rolls, stats.d20_rolls()), (3, 1));
+        assert_eq!(stats.average_d20(), Some(7.0));
+    }
+
+    #[test]
+    fn statistics_are_kept_per_session() {
+        let mut entries: Vec<_> = [20, 1, 1, 10].into_iter().map(|f| d20(1, f)).collect();
+        entries.push(d20(2, 20));
+        // Damage rolls count as rolls but not towards the d20 figures.
+        let mut damage = d20(2, 6);
+        damage.dice[0].sides = 8;
+        entries.push(damage);
+
+        let sessions = session_stats(&entries);
+        assert_eq!(
+            sessions.iter().map(|s| s.session).collect::<Vec<_>>(),
+            [2, 1]
+        );
+
+        let first = sessions[1].stats;
+        assert_eq!((first.rolls, first.d20_rolls()), (4, 4));
+        assert_eq!(first.average_d20(), Some(8.0));
+        assert_eq!((first.natural_20s(), first.natural_1s()), (1, 2));
+
+        let second = sessions[0].stats;
+        assert_eq!((second.rolls, second.d20_rolls()), (2, 1));
+        assert_eq!(second.average_d20(), Some(20.0));
+
+        let mut overall = RollStats::default();
+        assert_eq!(overall.average_d20(), None);
+        overall.combine(&first);
+        overall.combine(&second);
+        assert_eq!(overall.rolls, 6);
+        assert_eq!(overall.average_d20(), Some(10.4));
+        assert_eq!(overall.natural_20s(), 2);
+    }
+}
@@ -0,0 +1,118 @@
+/// A single line of text being typed, with a cursor that can be moved
+/// around in it. The cursor counts characters, not bytes.
+#[derive(Default)]
+pub struct TextInput {
+    value: String,
+    cursor: usize,
+}
+
+impl TextInput {
+    /// Starts with `value` already typed and the cursor at its end.
+    pub fn new(value: &str) -> TextInput {
+        TextInput {
+            value: value.to_string(),
+            cursor: value.chars().count(),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let at = self.byte_index();
+        self.value.insert(at, c);
+        self.cursor += 1;
+    }
+
+    /// Deletes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.cursor -= 1;
+        let at = self.byte_index();
+        self.value.remove(at);
+    }
+
+    /// Deletes the character under the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.value.chars().count() {
+            let at = self.byte_index();
+            self.value.remove(at);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.value.chars().count());
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.value.chars().count();
+    }
+
+    fn byte_index(&self) -> usize {
+        self.value
+            .char_indices()
+            .nth(self.cursor)
+            .map(|(i, _)| i)
+            .unwrap_or(self.value.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_non_ascii_text_by_character() {
+        let mut input = TextInput::new("Zoë");
+        assert_eq!(input.cursor(), 3);
+
+        input.backspace();
+        assert_eq!(input.value(), "Zo");
+        input.insert('é');
+        input.insert('!');
+        assert_eq!(input.value(), "Zoé!");
+
+        input.move_left();
+        input.move_left();
+        input.delete();
+        assert_eq!(input.value(), "Zo!");
+        assert_eq!(input.cursor(), 2);
+
+        input.move_home();
+        input.insert('¡');
+        assert_eq!(input.value(), "¡Zo!");
+        input.move_end();
+        input.insert('ü');
+        assert_eq!((input.value(), input.cursor()), ("¡Zo!ü", 5));
+    }
+
+    #[test]
+    fn stops_at_the_ends() {
+        let mut input = TextInput::new("Ænid");
+        input.delete();
+        input.move_right();
+        assert_eq!((input.value(), input.cursor()), ("Ænid", 4));
+
+        input.move_home();
+        input.backspace();
+        input.move_left();
+        assert_eq!((input.value(), input.cursor()), ("Ænid", 0));
+        input.delete();
+        assert_eq!(input.value(), "nid");
+    }
+}
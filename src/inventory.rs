@@ -0,0 +1,488 @@
+// What the character carries. Items can be put in containers, and
+// containers in other containers. Weights are in pounds and costs in copper
+// pieces, both for a single item of a stack.
+//
+// An item is found by its path: its index among the items carried, then its
+// index inside that container, and so on.
+
+use serde::{Deserialize, Serialize};
+
+use crate::rules;
+use crate::wallet;
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Inventory {
+    #[serde(default)]
+    pub items: Vec<Item>,
+    /// Uses the variant encumbrance rules, under which a heavy load costs
+    /// speed well before the carrying capacity is reached.
+    #[serde(default)]
+    pub variant_encumbrance: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Item {
+    pub name: String,
+    #[serde(default = "one")]
+    pub quantity: u32,
+    #[serde(default)]
+    pub weight: f64,
+    #[serde(default)]
+    pub cost_cp: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default)]
+    pub equipped: bool,
+    #[serde(default)]
+    pub attuned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<Container>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Container {
+    /// Pounds it can hold, no limit when left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<f64>,
+    #[serde(default)]
+    pub contents_weight: ContentsWeight,
+    #[serde(default)]
+    pub contents: Vec<Item>,
+}
+
+/// Whether what is inside a container adds to the weight carried.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentsWeight {
+    #[default]
+    Counted,
+    /// A bag of holding weighs the same however full it is.
+    Ignored,
+}
+
+// Containers from the equipment tables, set up when an item with one of
+// these names is made a container: (name, capacity, contents weight).
+const KNOWN_CONTAINERS: [(&str, f64, ContentsWeight); 9] = [
+    ("Backpack", 30.0, ContentsWeight::Counted),
+    ("Basket", 40.0, ContentsWeight::Counted),
+    ("Chest", 300.0, ContentsWeight::Counted),
+    ("Pouch", 6.0, ContentsWeight::Counted),
+    ("Sack", 30.0, ContentsWeight::Counted),
+    ("Quiver", 2.0, ContentsWeight::Counted),
+    ("Bag of Holding", 500.0, ContentsWeight::Ignored),
+    ("Handy Haversack", 120.0, ContentsWeight::Ignored),
+    ("Portable Hole", 10_000.0, ContentsWeight::Ignored),
+];
+
+impl Container {
+    /// The container an item called `name` would be: a known one such as a
+    /// backpack or a bag of holding, or one without a limit.
+    pub fn for_item(name: &str) -> Container {
+        let known = KNOWN_CONTAINERS
+            .iter()
+            .find(|(known, _, _)| known.eq_ignore_ascii_case(name.trim()));
+        match known {
+            Some(&(_, capacity, contents_weight)) => Container {
+                capacity: Some(capacity),
+                contents_weight,
+                contents: Vec::new(),
+            },
+            None => Container::default(),
+        }
+    }
+
+    /// Pounds inside, whatever the container does with them.
+    pub fn load(&self) -> f64 {
+        self.contents.iter().map(Item::total_weight).sum()
+    }
+
+    pub fn fits(&self, item: &Item) -> bool {
+        self.capacity
+            .is_none_or(|capacity| self.load() + item.total_weight() <= capacity)
+    }
+}
+
+impl Item {
+    pub fn new(name: &str) -> Item {
+        Item {
+            name: name.to_string(),
+            quantity: 1,
+            weight: 0.0,
+            cost_cp: 0,
+            description: String::new(),
+            equipped: false,
+            attuned: false,
+            container: None,
+        }
+    }
+
+    /// Reads an item typed as `name; quantity; weight; cost`, where all but
+    /// the name can be left off, e.g. `Torch; 10; 1; 1 cp`.
+    pub fn parse(text: &str) -> Result<Item, String> {
+        let mut fields = text.split(';').map(str::trim);
+        let name = fields.next().unwrap_or_default();
+        if name.is_empty() {
+            return Err("An item needs a name".into());
+        }
+        let mut item = Item::new(name);
+
+        if let Some(quantity) = fields.next().filter(|field| !field.is_empty()) {
+            item.quantity = match quantity.parse() {
+                Ok(quantity) if quantity > 0 => quantity,
+                _ => return Err(format!("'{quantity}' is not a quantity")),
+            };
+        }
+        if let Some(weight) = fields.next().filter(|field| !field.is_empty()) {
+            let weight = weight.trim_end_matches("lb").trim();
+            item.weight = match weight.parse::<f64>() {
+                Ok(weight) if weight >= 0.0 && weight.is_finite() => weight,
+                _ => return Err(format!("'{weight}' is not a weight in pounds")),
+            };
+        }
+        if let Some(cost) = fields.next().filter(|field| !field.is_empty()) {
+            item.cost_cp = wallet::parse_cost(cost)?;
+        }
+        if fields.next().is_some() {
+            return Err("Type name; quantity; weight; cost".into());
+        }
+        Ok(item)
+    }
+
+    /// The weight of the whole stack, and of anything inside that counts.
+    pub fn total_weight(&self) -> f64 {
+        let own = self.weight * f64::from(self.quantity);
+        match &self.container {
+            Some(container) if container.contents_weight == ContentsWeight::Counted => {
+                own + container.load()
+            }
+            _ => own,
+        }
+    }
+}
+
+/// Pounds to two decimal places at most, e.g. `7` or `0.25`.
+pub fn format_weight(weight: f64) -> String {
+    // Adding 0 turns the -0 an empty sum gives into a plain 0.
+    ((weight * 100.0).round() / 100.0 + 0.0).to_string()
+}
+
+/// How much a load slows the character down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encumbrance {
+    Unencumbered,
+    /// Variant rules only.
+    Encumbered,
+    /// Variant rules only.
+    HeavilyEncumbered,
+    OverCapacity,
+}
+
+impl Encumbrance {
+    pub fn label(self) -> &'static str {
+        match self {
+            Encumbrance::Unencumbered => "Unencumbered",
+            Encumbrance::Encumbered => "Encumbered",
+            Encumbrance::HeavilyEncumbered => "Heavily encumbered",
+            Encumbrance::OverCapacity => "Over capacity",
+        }
+    }
+}
+
+impl Inventory {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && !self.variant_encumbrance
+    }
+
+    /// Pounds carried, counting containers by their own rules.
+    pub fn total_weight(&self) -> f64 {
+        self.items.iter().map(Item::total_weight).sum()
+    }
+
+    pub fn encumbrance(&self, strength: u8) -> Encumbrance {
+        let weight = self.total_weight();
+        if weight > rules::carrying_capacity(strength) {
+            Encumbrance::OverCapacity
+        } else if !self.variant_encumbrance {
+            Encumbrance::Unencumbered
+        } else if weight > rules::heavily_encumbered_above(strength) {
+            Encumbrance::HeavilyEncumbered
+        } else if weight > rules::encumbered_above(strength) {
+            Encumbrance::Encumbered
+        } else {
+            Encumbrance::Unencumbered
+        }
+    }
+
+    /// Feet of speed lost to the load. Only the variant rules cost speed.
+    pub fn speed_penalty(&self, strength: u8) -> u8 {
+        if !self.variant_encumbrance {
+            return 0;
+        }
+        match self.encumbrance(strength) {
+            Encumbrance::Unencumbered => 0,
+            Encumbrance::Encumbered => rules::ENCUMBERED_SPEED_PENALTY,
+            Encumbrance::HeavilyEncumbered | Encumbrance::OverCapacity => {
+                rules::HEAVILY_ENCUMBERED_SPEED_PENALTY
+            }
+        }
+    }
+
+    pub fn attuned(&self) -> usize {
+        self.rows().iter().filter(|(_, item)| item.attuned).count()
+    }
+
+    /// Every item with its path, each container followed by what is in it.
+    pub fn rows(&self) -> Vec<(Vec<usize>, &Item)> {
+        fn walk<'a>(items: &'a [Item], parent: &[usize], rows: &mut Vec<(Vec<usize>, &'a Item)>) {
+            for (i, item) in items.iter().enumerate() {
+                let mut path = parent.to_vec();
+                path.push(i);
+                rows.push((path.clone(), item));
+                if let Some(container) = &item.container {
+                    walk(&container.contents, &path, rows);
+                }
+            }
+        }
+        let mut rows = Vec::new();
+        walk(&self.items, &[], &mut rows);
+        rows
+    }
+
+    pub fn get(&self, path: &[usize]) -> Option<&Item> {
+        let (last, parent) = path.split_last()?;
+        self.list(parent)?.get(*last)
+    }
+
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Item> {
+        let (last, parent) = path.split_last()?;
+        self.list_mut(parent)?.get_mut(*last)
+    }
+
+    /// The items carried for an empty `parent`, otherwise the contents of
+    /// the container at `parent`.
+    fn list(&self, parent: &[usize]) -> Option<&Vec<Item>> {
+        match parent.split_last() {
+            None => Some(&self.items),
+            Some(_) => Some(&self.get(parent)?.container.as_ref()?.contents),
+        }
+    }
+
+    fn list_mut(&mut self, parent: &[usize]) -> Option<&mut Vec<Item>> {
+        match parent.split_last() {
+            None => Some(&mut self.items),
+            Some(_) => Some(&mut self.get_mut(parent)?.container.as_mut()?.contents),
+        }
+    }
+
+    /// Takes the item at `path` out of the inventory. Whatever was inside a
+    /// container is left where the container was rather than thrown away.
+    pub fn remove(&mut self, path: &[usize]) -> Option<Item> {
+        let (last, parent) = path.split_last()?;
+        let list = self.list_mut(parent)?;
+        if *last >= list.len() {
+            return None;
+        }
+        let mut item = list.remove(*last);
+        if let Some(container) = &mut item.container {
+            let contents = std::mem::take(&mut container.contents);
+            list.splice(*last..*last, contents);
+        }
+        Some(item)
+    }
+
+    /// Where the item at `path` goes on the next press of move: the next
+    /// container after the one it is in that it fits in, or back to being
+    /// carried. Never into itself or into something inside it.
+    pub fn next_destination(&self, path: &[usize]) -> Option<Vec<usize>> {
+        let item = self.get(path)?;
+        let parent = &path[..path.len() - 1];
+        let mut destinations = vec![Vec::new()];
+        destinations.extend(
+            self.rows()
+                .into_iter()
+                .filter(|(_, item)| item.container.is_some())
+                .map(|(path, _)| path),
+        );
+
+        let start = destinations.iter().position(|d| d == parent)?;
+        destinations
+            .iter()
+            .cycle()
+            .skip(start + 1)
+            .take(destinations.len() - 1)
+            .find(|destination| {
+                !destination.starts_with(path)
+                    && (destination.is_empty()
+                        || self
+                            .get(destination)
+                            .and_then(|d| d.container.as_ref())
+                            .is_some_and(|container| container.fits(item)))
+            })
+            .cloned()
+    }
+
+    /// Moves the item at `path` to the end of the container at
+    /// `destination`, or of the items carried when it is empty. Returns the
+    /// path of the item afterwards.
+    pub fn move_item(&mut self, path: &[usize], destination: &[usize]) -> Option<Vec<usize>> {
+        if destination.starts_with(path) {
+            return None;
+        }
+        self.list(destination)?;
+        self.get(path)?;
+        let (last, parent) = path.split_last()?;
+        let item = self.list_mut(parent)?.remove(*last);
+
+        // Taking the item out shifts everything after it up by one.
+        let mut destination = destination.to_vec();
+        if destination.len() > parent.len()
+            && destination.starts_with(parent)
+            && destination[parent.len()] > *last
+        {
+            destination[parent.len()] -= 1;
+        }
+        let list = self.list_mut(&destination)?;
+        list.push(item);
+        destination.push(list.len() - 1);
+        Some(destination)
+    }
+
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        for (_, item) in self.rows() {
+            if item.weight < 0.0 || !item.weight.is_finite() {
+                problems.push(format!("{} has a weight of {}", item.name, item.weight));
+            }
+        }
+        let attuned = self.attuned();
+        if attuned > rules::MAX_ATTUNED_ITEMS {
+            problems.push(format!(
+                "{attuned} items are attuned but only {} can be",
+                rules::MAX_ATTUNED_ITEMS
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backpack() -> Item {
+        let mut backpack = Item::new("Backpack");
+        backpack.weight = 5.0;
+        backpack.container = Some(Container::for_item("Backpack"));
+        backpack
+    }
+
+    fn item(name: &str, weight: f64) -> Item {
+        let mut item = Item::new(name);
+        item.weight = weight;
+        item
+    }
+
+    fn names(inventory: &Inventory) -> Vec<(Vec<usize>, String)> {
+        inventory
+            .rows()
+            .into_iter()
+            .map(|(path, item)| (path, item.name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_typed_items() {
+        let torch = Item::parse("Torch; 10; 1 lb; 1 cp").unwrap();
+        assert_eq!(
+            (
+                torch.name.as_str(),
+                torch.quantity,
+                torch.weight,
+                torch.cost_cp
+            ),
+            ("Torch", 10, 1.0, 1)
+        );
+        let rope = Item::parse(" Rope ;; 10").unwrap();
+        assert_eq!((rope.quantity, rope.weight, rope.cost_cp), (1, 10.0, 0));
+        assert_eq!(Item::parse("Sword; 1; 3; 15").unwrap().cost_cp, 1500);
+        assert!(Item::parse("; 2").is_err());
+        assert!(Item::parse("Torch; none").is_err());
+        assert!(Item::parse("Torch; 1; 1; 5 xp").is_err());
+    }
+
+    #[test]
+    fn containers_follow_their_weight_rules() {
+        let mut pack = backpack();
+        pack.container
+            .as_mut()
+            .unwrap()
+            .contents
+            .push(item("Bedroll", 7.0));
+        let mut bag = item("Bag of Holding", 15.0);
+        bag.container = Some(Container::for_item("bag of holding"));
+        bag.container
+            .as_mut()
+            .unwrap()
+            .contents
+            .push(item("Anvil", 100.0));
+
+        assert_eq!(pack.total_weight(), 12.0);
+        assert_eq!(bag.total_weight(), 15.0);
+        assert!(!pack.container.as_ref().unwrap().fits(&item("Anvil", 100.0)));
+    }
+
+    #[test]
+    fn moves_items_between_containers() {
+        let mut inventory = Inventory {
+            items: vec![item("Rope", 10.0), backpack(), backpack()],
+            variant_encumbrance: false,
+        };
+
+        // The rope goes into the first backpack, whose index moves up by one
+        // once the rope is out of the way.
+        assert_eq!(inventory.next_destination(&[0]), Some(vec![1]));
+        assert_eq!(inventory.move_item(&[0], &[1]), Some(vec![0, 0]));
+        assert_eq!(inventory.next_destination(&[0, 0]), Some(vec![1]));
+        // A backpack can't go into itself.
+        assert_eq!(inventory.move_item(&[0], &[0]), None);
+        assert_eq!(inventory.move_item(&[0], &[1]), Some(vec![0, 0]));
+        assert_eq!(
+            names(&inventory),
+            [
+                (vec![0], "Backpack".to_string()),
+                (vec![0, 0], "Backpack".to_string()),
+                (vec![0, 0, 0], "Rope".to_string()),
+            ]
+        );
+        assert_eq!(inventory.total_weight(), 20.0);
+
+        // Removing a container leaves what was in it behind.
+        inventory.remove(&[0, 0]);
+        assert_eq!(
+            names(&inventory),
+            [
+                (vec![0], "Backpack".to_string()),
+                (vec![0, 0], "Rope".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn variant_encumbrance_costs_speed() {
+        let mut inventory = Inventory::default();
+        inventory.items.push(item("Anvil", 60.0));
+        assert_eq!(inventory.encumbrance(10), Encumbrance::Unencumbered);
+        assert_eq!(inventory.speed_penalty(10), 0);
+
+        inventory.variant_encumbrance = true;
+        assert_eq!(inventory.encumbrance(10), Encumbrance::Encumbered);
+        assert_eq!(inventory.speed_penalty(10), 10);
+        inventory.items.push(item("Anvil", 60.0));
+        assert_eq!(inventory.encumbrance(10), Encumbrance::HeavilyEncumbered);
+        inventory.items.push(item("Anvil", 60.0));
+        assert_eq!(inventory.encumbrance(10), Encumbrance::OverCapacity);
+        assert_eq!(inventory.speed_penalty(10), 20);
+    }
+}
@@ -0,0 +1,15 @@
+//! The character sheet model, the game rules computed from it and the code
+//! that reads and writes sheet files. The `char-sheet` binary is a terminal
+//! frontend over this crate; other tools can depend on it directly.
+
+pub mod dice;
+pub mod history;
+pub mod inventory;
+pub mod migrate;
+pub mod resource;
+pub mod rest;
+pub mod rules;
+pub mod sheet;
+pub mod spells;
+pub mod storage;
+pub mod wallet;
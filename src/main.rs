@@ -1,12 +1,12 @@
 use ratatui::layout::Rect;
-use std::{error::Error, io};
+use std::{env, error::Error, io, path::Path, process};
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
         event::{
             self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
-            MouseButton, MouseEventKind,
+            KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
         },
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -15,13 +15,58 @@ use ratatui::{
 };
 
 mod app;
+mod cli;
+mod commands;
+mod input;
+mod theme;
 mod ui;
+use char_sheet::dice::Advantage;
+use char_sheet::rest::RestKind;
+use char_sheet::sheet::{Ability, HpChangeKind, LifeState, RollTarget};
+
 use crate::{
-    app::{App, CurrentScreen, HealthView, Hover, ViewState},
+    app::{
+        offset_ability, offset_roll_target, App, CurrEditInformation, CurrentScreen, HealthView,
+        Hover, InfoView, MoneyKind, PathAction, PathPrompt, ProficiencyTarget, StatsView,
+        ViewState,
+    },
+    cli::Command,
+    input::TextInput,
     ui::ui,
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Headless { command, json }) => process::exit(commands::run(command, json)),
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Ok(Command::Version) => {
+            println!("char-sheet {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{}", cli::USAGE);
+            process::exit(commands::EXIT_USAGE);
+        }
+    };
+
+    // Catch the obvious mistakes before the terminal is taken over.
+    let exists = Path::new(&options.sheet).exists();
+    if !options.new && !exists {
+        eprintln!(
+            "error: sheet file '{}' does not exist (use --new to create it)",
+            options.sheet
+        );
+        process::exit(1);
+    }
+    if options.new && exists {
+        eprintln!("error: '{}' already exists", options.sheet);
+        process::exit(1);
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stderr = io::stderr(); // This is a special case. Normally using stdout is fine
@@ -34,11 +79,16 @@ fn main() -> Result<(), Box<dyn Error>> {
             minus_rect: Rect::new(0, 0, 0, 0),
             plus_rect: Rect::new(0, 0, 0, 0),
             hover: Hover::None,
+            ..HealthView::default()
         },
+        info: InfoView::default(),
+        stats: StatsView::default(),
+        roll_log: Rect::default(),
+        resources: Vec::new(),
     };
 
     // create app and run it
-    let mut app = App::new("resources/character_sheet.json".to_string());
+    let mut app = App::new(options);
     let res = run_app(&mut terminal, &mut app, &mut view_state);
 
     // restore terminal
@@ -63,17 +113,550 @@ fn rect_contains(rect: Rect, x: u16, y: u16) -> bool {
 
 enum Action {
     Quit,
+    Save,
+    SaveAndQuit,
+    DiscardAndQuit,
+    CancelQuit,
+    ShowBackups,
+    BackupPrevious,
+    BackupNext,
+    BackupRestore,
+    CloseBackups,
     HpIncrease,
     HpDecrease,
+    PromptHp(HpChangeKind),
+    RollDeathSave,
+    StartRolling,
+    MoveRollTarget(isize, isize),
+    RollCheck(RollTarget, Option<Advantage>),
+    CycleAdvantage,
+    ScrollRollLog(isize),
+    ShowRollStats,
+    RollStatsPrevious,
+    RollStatsNext,
+    CloseRollStats,
+    ShowSpells,
+    MoveSpell(isize),
+    ShiftCastSlot(isize),
+    CastSpell,
+    TogglePrepared,
+    TogglePreparation,
+    RegainSlot,
+    AdjustMaxSlots(i8),
+    AdjustPactLevel(i8),
+    CycleCastingAbility,
+    ShowResources,
+    MoveResource(isize),
+    SpendResource(usize),
+    RestoreResource(usize),
+    RefillResource,
+    ShowInventory,
+    MoveItemSelection(isize),
+    PromptItem,
+    AdjustItemQuantity(i32),
+    RemoveItem,
+    MoveItem,
+    ToggleEquipped,
+    ToggleAttuned,
+    ToggleContainer,
+    ToggleVariantEncumbrance,
+    ShowWallet,
+    PromptMoney(MoneyKind),
+    ConsolidateCoins,
+    ScrollLedger(isize),
+    StartRest(RestKind),
+    SpendHitDie,
+    UndoHitDie,
+    FinishRest,
+    CancelRest,
+    RecordDeathSave(bool),
+    PromptPath(PathAction),
+    StartEditing,
+    StopEditing,
+    SelectField(CurrEditInformation),
+    MoveField(isize),
+    EditField,
+    StartAbilityEditing,
+    SelectAbility(Ability),
+    MoveAbility(isize),
+    AdjustAbility(i8),
+    StartProficiencyEditing,
+    MoveProficiency(isize, isize),
+    ToggleProficiency(ProficiencyTarget),
+    InputChar(char),
+    InputBackspace,
+    InputDelete,
+    InputLeft,
+    InputRight,
+    InputHome,
+    InputEnd,
+    InputSubmit,
+    InputCancel,
     None,
 }
 
-fn handle_event(event: Event, view: &mut HealthView) -> Action {
+/// Keys shared by every text input.
+fn handle_input_key(code: KeyCode) -> Action {
+    match code {
+        KeyCode::Enter => Action::InputSubmit,
+        KeyCode::Esc => Action::InputCancel,
+        KeyCode::Backspace => Action::InputBackspace,
+        KeyCode::Delete => Action::InputDelete,
+        KeyCode::Left => Action::InputLeft,
+        KeyCode::Right => Action::InputRight,
+        KeyCode::Home => Action::InputHome,
+        KeyCode::End => Action::InputEnd,
+        KeyCode::Char(c) => Action::InputChar(c),
+        _ => Action::None,
+    }
+}
+
+fn handle_load_error_event(event: Event, prompting: bool) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+
+    match (prompting, key.code) {
+        (true, code) => handle_input_key(code),
+        (false, KeyCode::Char('o')) => Action::PromptPath(PathAction::Open),
+        (false, KeyCode::Char('n')) => Action::PromptPath(PathAction::New),
+        (false, KeyCode::Char('q') | KeyCode::Esc) => Action::Quit,
+        _ => Action::None,
+    }
+}
+
+fn handle_exit_event(event: Event) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+
+    match key.code {
+        KeyCode::Char('s' | 'y') => Action::SaveAndQuit,
+        KeyCode::Char('d' | 'n') => Action::DiscardAndQuit,
+        KeyCode::Char('c') | KeyCode::Esc => Action::CancelQuit,
+        _ => Action::None,
+    }
+}
+
+fn handle_rest_event(event: Event) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+
+    match key.code {
+        KeyCode::Char(' ' | '+') => Action::SpendHitDie,
+        KeyCode::Backspace | KeyCode::Char('-') => Action::UndoHitDie,
+        KeyCode::Enter => Action::FinishRest,
+        KeyCode::Esc | KeyCode::Char('q') => Action::CancelRest,
+        _ => Action::None,
+    }
+}
+
+fn handle_backups_event(event: Event) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+
+    match key.code {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+        KeyCode::Up | KeyCode::Char('k') => Action::BackupPrevious,
+        KeyCode::Down | KeyCode::Char('j') => Action::BackupNext,
+        KeyCode::Enter => Action::BackupRestore,
+        KeyCode::Esc | KeyCode::Char('b' | 'q') => Action::CloseBackups,
+        _ => Action::None,
+    }
+}
+
+fn handle_roll_stats_event(event: Event) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => Action::RollStatsPrevious,
+        KeyCode::Down | KeyCode::Char('j') => Action::RollStatsNext,
+        KeyCode::Esc | KeyCode::Char('s' | 'q') => Action::CloseRollStats,
+        _ => Action::None,
+    }
+}
+
+fn handle_spells_event(event: Event) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+
+    match key.code {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+        KeyCode::Up | KeyCode::Char('k') => Action::MoveSpell(-1),
+        KeyCode::Down | KeyCode::Char('j') => Action::MoveSpell(1),
+        KeyCode::Left | KeyCode::Char('h') => Action::ShiftCastSlot(-1),
+        KeyCode::Right | KeyCode::Char('l') => Action::ShiftCastSlot(1),
+        KeyCode::Enter => Action::CastSpell,
+        KeyCode::Char('p') | KeyCode::Char(' ') => Action::TogglePrepared,
+        KeyCode::Char('P') => Action::TogglePreparation,
+        KeyCode::Char('u') => Action::RegainSlot,
+        KeyCode::Char('+') | KeyCode::Char('=') => Action::AdjustMaxSlots(1),
+        KeyCode::Char('-') => Action::AdjustMaxSlots(-1),
+        KeyCode::Char('>') | KeyCode::Char('.') => Action::AdjustPactLevel(1),
+        KeyCode::Char('<') | KeyCode::Char(',') => Action::AdjustPactLevel(-1),
+        KeyCode::Char('a') => Action::CycleCastingAbility,
+        KeyCode::Esc | KeyCode::Char('c') => Action::StopEditing,
+        KeyCode::Char('q') => Action::Quit,
+        _ => Action::None,
+    }
+}
+
+fn handle_resources_event(event: Event, selected: usize, view_state: &ViewState) -> Action {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+            KeyCode::Up | KeyCode::Char('k') => Action::MoveResource(-1),
+            KeyCode::Down | KeyCode::Char('j') => Action::MoveResource(1),
+            KeyCode::Enter | KeyCode::Char(' ' | '-') => Action::SpendResource(selected),
+            KeyCode::Char('+' | '=') => Action::RestoreResource(selected),
+            KeyCode::Char('f') => Action::RefillResource,
+            KeyCode::Esc | KeyCode::Char('u') => Action::StopEditing,
+            KeyCode::Char('q') => Action::Quit,
+            _ => Action::None,
+        },
+        // A left click on a resource spends one, a right click gets one back.
+        Event::Mouse(mouse) => {
+            let Some(index) = view_state
+                .resources
+                .iter()
+                .position(|rect| rect_contains(*rect, mouse.column, mouse.row))
+            else {
+                return Action::None;
+            };
+            match mouse.kind {
+                MouseEventKind::Up(MouseButton::Left) => Action::SpendResource(index),
+                MouseEventKind::Up(MouseButton::Right) => Action::RestoreResource(index),
+                _ => Action::None,
+            }
+        }
+        _ => Action::None,
+    }
+}
+
+fn handle_inventory_event(event: Event, typing: bool) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+    if typing {
+        return handle_input_key(key.code);
+    }
+
+    match key.code {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+        KeyCode::Up | KeyCode::Char('k') => Action::MoveItemSelection(-1),
+        KeyCode::Down | KeyCode::Char('j') => Action::MoveItemSelection(1),
+        KeyCode::Char('a') => Action::PromptItem,
+        KeyCode::Char('+' | '=') => Action::AdjustItemQuantity(1),
+        KeyCode::Char('-') => Action::AdjustItemQuantity(-1),
+        KeyCode::Char('x') | KeyCode::Delete => Action::RemoveItem,
+        KeyCode::Char('m') => Action::MoveItem,
+        KeyCode::Char('e') | KeyCode::Enter => Action::ToggleEquipped,
+        KeyCode::Char('t') => Action::ToggleAttuned,
+        KeyCode::Char('c') => Action::ToggleContainer,
+        KeyCode::Char('v') => Action::ToggleVariantEncumbrance,
+        KeyCode::Esc | KeyCode::Char('i') => Action::StopEditing,
+        KeyCode::Char('q') => Action::Quit,
+        _ => Action::None,
+    }
+}
+
+fn handle_wallet_event(event: Event, typing: bool) -> Action {
+    let Event::Key(key) = event else {
+        return Action::None;
+    };
+    if key.kind != KeyEventKind::Press {
+        return Action::None;
+    }
+    if typing {
+        return handle_input_key(key.code);
+    }
+
+    match key.code {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+        KeyCode::Char('p') => Action::PromptMoney(MoneyKind::Pay),
+        KeyCode::Char('r') => Action::PromptMoney(MoneyKind::Receive),
+        KeyCode::Char('c') => Action::ConsolidateCoins,
+        KeyCode::Up | KeyCode::Char('k') => Action::ScrollLedger(-1),
+        KeyCode::Down | KeyCode::Char('j') => Action::ScrollLedger(1),
+        KeyCode::Esc | KeyCode::Char('w') => Action::StopEditing,
+        KeyCode::Char('q') => Action::Quit,
+        _ => Action::None,
+    }
+}
+
+fn handle_editing_event(event: Event, typing: bool, view_state: &ViewState) -> Action {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => {
+            if typing {
+                return handle_input_key(key.code);
+            }
+            match key.code {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+                KeyCode::Up | KeyCode::Char('k') => Action::MoveField(-1),
+                KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => Action::MoveField(1),
+                // The panel has three fields per column.
+                KeyCode::Left | KeyCode::Char('h') => Action::MoveField(-3),
+                KeyCode::Right | KeyCode::Char('l') => Action::MoveField(3),
+                KeyCode::Enter => Action::EditField,
+                KeyCode::Esc | KeyCode::Char('e') => Action::StopEditing,
+                KeyCode::Char('q') => Action::Quit,
+                _ => Action::None,
+            }
+        }
+        Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Up(MouseButton::Left)) => {
+            handle_sheet_click(view_state, mouse.column, mouse.row)
+        }
+        _ => Action::None,
+    }
+}
+
+fn handle_abilities_event(event: Event, typing: bool, view_state: &ViewState) -> Action {
     match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => {
+            if typing {
+                return handle_input_key(key.code);
+            }
+            match key.code {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+                // The boxes are laid out two per row.
+                KeyCode::Up | KeyCode::Char('k') => Action::MoveAbility(-2),
+                KeyCode::Down | KeyCode::Char('j') => Action::MoveAbility(2),
+                KeyCode::Left | KeyCode::Char('h') => Action::MoveAbility(-1),
+                KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => Action::MoveAbility(1),
+                KeyCode::Char('+' | '=') => Action::AdjustAbility(1),
+                KeyCode::Char('-') => Action::AdjustAbility(-1),
+                KeyCode::Enter => Action::EditField,
+                KeyCode::Esc | KeyCode::Char('a') => Action::StopEditing,
+                KeyCode::Char('q') => Action::Quit,
+                _ => Action::None,
+            }
+        }
+        Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Up(MouseButton::Left)) => {
+            handle_sheet_click(view_state, mouse.column, mouse.row)
+        }
+        _ => Action::None,
+    }
+}
+
+fn handle_proficiencies_event(
+    event: Event,
+    selected: ProficiencyTarget,
+    view_state: &ViewState,
+) -> Action {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+            KeyCode::Up | KeyCode::Char('k') => Action::MoveProficiency(0, -1),
+            KeyCode::Down | KeyCode::Char('j') => Action::MoveProficiency(0, 1),
+            KeyCode::Left | KeyCode::Char('h') => Action::MoveProficiency(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') => Action::MoveProficiency(1, 0),
+            KeyCode::Enter | KeyCode::Char(' ') => Action::ToggleProficiency(selected),
+            KeyCode::Esc | KeyCode::Char('p') => Action::StopEditing,
+            KeyCode::Char('q') => Action::Quit,
+            _ => Action::None,
+        },
+        Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Up(MouseButton::Left)) => {
+            handle_sheet_click(view_state, mouse.column, mouse.row)
+        }
+        _ => Action::None,
+    }
+}
+
+fn handle_rolling_event(event: Event, selected: RollTarget, view_state: &ViewState) -> Action {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Save,
+            KeyCode::Up | KeyCode::Char('k') => Action::MoveRollTarget(0, -1),
+            KeyCode::Down | KeyCode::Char('j') => Action::MoveRollTarget(0, 1),
+            KeyCode::Left | KeyCode::Char('h') => Action::MoveRollTarget(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') => Action::MoveRollTarget(1, 0),
+            KeyCode::Enter | KeyCode::Char(' ') => Action::RollCheck(selected, None),
+            KeyCode::Char('a') => Action::RollCheck(selected, Some(Advantage::Advantage)),
+            KeyCode::Char('d') => Action::RollCheck(selected, Some(Advantage::Disadvantage)),
+            KeyCode::Char('i') => Action::RollCheck(RollTarget::Initiative, None),
+            KeyCode::Char('v') => Action::CycleAdvantage,
+            KeyCode::Char('s') => Action::ShowRollStats,
+            KeyCode::PageUp => Action::ScrollRollLog(1),
+            KeyCode::PageDown => Action::ScrollRollLog(-1),
+            KeyCode::Esc | KeyCode::Char('r') => Action::StopEditing,
+            KeyCode::Char('q') => Action::Quit,
+            _ => Action::None,
+        },
+        Event::Mouse(mouse) => handle_roll_mouse(view_state, mouse).unwrap_or(Action::None),
+        _ => Action::None,
+    }
+}
+
+/// On the Main and Roll screens clicking an ability box, saving throw or
+/// skill rolls it. Shift or Alt held while clicking rolls with advantage,
+/// Ctrl with disadvantage, though many terminals keep Shift-clicks for
+/// selecting text; (v) sets up advantage from the keyboard instead. A right
+/// click on a saving throw or skill changes its proficiency, and the wheel
+/// scrolls the roll log.
+fn handle_roll_mouse(view_state: &ViewState, mouse: MouseEvent) -> Option<Action> {
+    let (x, y) = (mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Up(MouseButton::Left) => {
+            let advantage = if mouse.modifiers.contains(KeyModifiers::CONTROL) {
+                Some(Advantage::Disadvantage)
+            } else if mouse
+                .modifiers
+                .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT)
+            {
+                Some(Advantage::Advantage)
+            } else {
+                None
+            };
+            let target = clicked_ability(&view_state.stats, x, y)
+                .map(RollTarget::Ability)
+                .or_else(|| {
+                    clicked_proficiency(&view_state.stats, x, y).map(ProficiencyTarget::roll_target)
+                })?;
+            Some(Action::RollCheck(target, advantage))
+        }
+        MouseEventKind::Up(MouseButton::Right) => {
+            clicked_proficiency(&view_state.stats, x, y).map(Action::ToggleProficiency)
+        }
+        MouseEventKind::ScrollUp if rect_contains(view_state.roll_log, x, y) => {
+            Some(Action::ScrollRollLog(1))
+        }
+        MouseEventKind::ScrollDown if rect_contains(view_state.roll_log, x, y) => {
+            Some(Action::ScrollRollLog(-1))
+        }
+        _ => None,
+    }
+}
+
+/// On the edit screens clicking an information field or an ability box
+/// selects it for editing, and clicking a saving throw or skill changes its
+/// proficiency straight away. On the Main screen `handle_roll_mouse` gets
+/// the click first, so only the information fields end up here.
+fn handle_sheet_click(view_state: &ViewState, x: u16, y: u16) -> Action {
+    if let Some(field) = clicked_field(&view_state.info, x, y) {
+        Action::SelectField(field)
+    } else if let Some(ability) = clicked_ability(&view_state.stats, x, y) {
+        Action::SelectAbility(ability)
+    } else if let Some(target) = clicked_proficiency(&view_state.stats, x, y) {
+        Action::ToggleProficiency(target)
+    } else {
+        Action::None
+    }
+}
+
+fn clicked_field(view: &InfoView, x: u16, y: u16) -> Option<CurrEditInformation> {
+    view.field_rects
+        .iter()
+        .find(|(_, rect)| rect_contains(*rect, x, y))
+        .map(|(field, _)| *field)
+}
+
+fn clicked_ability(view: &StatsView, x: u16, y: u16) -> Option<Ability> {
+    view.ability_rects
+        .iter()
+        .find(|(_, rect)| rect_contains(*rect, x, y))
+        .map(|(ability, _)| *ability)
+}
+
+fn clicked_proficiency(view: &StatsView, x: u16, y: u16) -> Option<ProficiencyTarget> {
+    view.proficiency_rects
+        .iter()
+        .find(|(_, rect)| rect_contains(*rect, x, y))
+        .map(|(target, _)| *target)
+}
+
+fn handle_event(event: Event, app: &App, view_state: &mut ViewState) -> Action {
+    match app.current_screen {
+        CurrentScreen::LoadFailed => {
+            return handle_load_error_event(event, app.path_prompt.is_some());
+        }
+        CurrentScreen::Exiting => return handle_exit_event(event),
+        CurrentScreen::Backups => return handle_backups_event(event),
+        CurrentScreen::RollStats => return handle_roll_stats_event(event),
+        CurrentScreen::Spells => return handle_spells_event(event),
+        CurrentScreen::Resources => {
+            return handle_resources_event(event, app.selected_resource, view_state);
+        }
+        CurrentScreen::Inventory => {
+            return handle_inventory_event(event, app.item_prompt.is_some());
+        }
+        CurrentScreen::Wallet => return handle_wallet_event(event, app.money_prompt.is_some()),
+        CurrentScreen::Resting => return handle_rest_event(event),
+        CurrentScreen::Editing => {
+            return handle_editing_event(event, app.edit_input.is_some(), view_state);
+        }
+        CurrentScreen::EditingAbilities => {
+            return handle_abilities_event(event, app.edit_input.is_some(), view_state);
+        }
+        CurrentScreen::EditingProficiencies => {
+            return handle_proficiencies_event(event, app.edit_proficiency, view_state);
+        }
+        CurrentScreen::Rolling => return handle_rolling_event(event, app.roll_target, view_state),
+        CurrentScreen::Main => {}
+    }
+
+    if app.hp_prompt.is_some() {
+        return match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => handle_input_key(key.code),
+            _ => Action::None,
+        };
+    }
+
+    let view = &mut view_state.health;
+    match event {
+        Event::Key(key)
+            if key.kind == KeyEventKind::Press
+                && key.code == KeyCode::Char('s')
+                && key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            Action::Save
+        }
+
         Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q') => {
             Action::Quit
         }
 
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('b') => {
+            Action::ShowBackups
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('e') => {
+            Action::StartEditing
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('a') => {
+            Action::StartAbilityEditing
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('p') => {
+            Action::StartProficiencyEditing
+        }
+
         Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('+') => {
             Action::HpIncrease
         }
@@ -82,6 +665,54 @@ fn handle_event(event: Event, view: &mut HealthView) -> Action {
             Action::HpDecrease
         }
 
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('d') => {
+            Action::PromptHp(HpChangeKind::Damage)
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('h') => {
+            Action::PromptHp(HpChangeKind::Healing)
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('t') => {
+            Action::PromptHp(HpChangeKind::TemporaryHp)
+        }
+
+        // A dying character can't do much besides death saves.
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('r') => {
+            match app.char_sheet.health.life_state() {
+                LifeState::Dying => Action::RollDeathSave,
+                _ => Action::StartRolling,
+            }
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('s') => {
+            Action::StartRest(RestKind::Short)
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('l') => {
+            Action::StartRest(RestKind::Long)
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('v') => {
+            Action::CycleAdvantage
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('c') => {
+            Action::ShowSpells
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('u') => {
+            Action::ShowResources
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('i') => {
+            Action::ShowInventory
+        }
+
+        Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('w') => {
+            Action::ShowWallet
+        }
+
         Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Up(MouseButton::Left)) => {
             if rect_contains(view.minus_rect, mouse.column, mouse.row) {
                 view.hover = Hover::Minus;
@@ -89,15 +720,34 @@ fn handle_event(event: Event, view: &mut HealthView) -> Action {
             } else if rect_contains(view.plus_rect, mouse.column, mouse.row) {
                 view.hover = Hover::Plus;
                 Action::HpIncrease
+            } else if rect_contains(view.roll_rect, mouse.column, mouse.row) {
+                Action::RollDeathSave
+            } else if rect_contains(view.success_rect, mouse.column, mouse.row) {
+                Action::RecordDeathSave(true)
+            } else if rect_contains(view.failure_rect, mouse.column, mouse.row) {
+                Action::RecordDeathSave(false)
             } else {
                 view.hover = Hover::None;
-                Action::None
+                handle_roll_mouse(view_state, mouse)
+                    .unwrap_or_else(|| handle_sheet_click(view_state, mouse.column, mouse.row))
             }
         }
+        Event::Mouse(mouse) => handle_roll_mouse(view_state, mouse).unwrap_or(Action::None),
         _ => Action::None,
     }
 }
 
+/// The text input that currently has the keyboard, if any.
+fn active_input(app: &mut App) -> Option<&mut TextInput> {
+    match app.current_screen {
+        CurrentScreen::Editing | CurrentScreen::EditingAbilities => app.edit_input.as_mut(),
+        CurrentScreen::Main => app.hp_prompt.as_mut().map(|prompt| &mut prompt.input),
+        CurrentScreen::Inventory => app.item_prompt.as_mut(),
+        CurrentScreen::Wallet => app.money_prompt.as_mut().map(|prompt| &mut prompt.input),
+        _ => app.path_prompt.as_mut().map(|prompt| &mut prompt.input),
+    }
+}
+
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
@@ -108,27 +758,472 @@ fn run_app<B: Backend>(
         let timeout = std::time::Duration::from_millis(250);
 
         if event::poll(timeout)? {
-            match handle_event(event::read()?, &mut view_state.health) {
+            let action = handle_event(event::read()?, app, view_state);
+            // Status messages last until the next thing the user does.
+            if !matches!(action, Action::None) {
+                app.status = None;
+            }
+
+            match action {
                 Action::Quit => {
+                    // Only ask about saving when there is something to lose.
+                    if !app.dirty || matches!(app.current_screen, CurrentScreen::LoadFailed) {
+                        break Ok(false);
+                    }
                     app.current_screen = CurrentScreen::Exiting;
-                    break Ok(false);
                 }
 
-                Action::HpIncrease => {
-                    app.char_sheet.health.current_hp = (app.char_sheet.health.current_hp + 1)
-                        .min(app.char_sheet.health.maximum_hp);
+                Action::Save => match app.save() {
+                    Ok(()) if matches!(app.current_screen, CurrentScreen::Backups) => {
+                        // The save pushed every backup down one.
+                        let selected = app.selected_backup + 1;
+                        app.show_backups();
+                        app.selected_backup = selected.min(app.backups.len().saturating_sub(1));
+                    }
+                    Ok(()) => {}
+                    Err(err) => app.status = Some(format!("Save failed: {err}")),
+                },
+
+                Action::SaveAndQuit => match app.save() {
+                    Ok(()) => break Ok(true),
+                    Err(err) => {
+                        app.status = Some(format!("Save failed: {err}"));
+                        app.current_screen = CurrentScreen::Main;
+                    }
+                },
+
+                Action::DiscardAndQuit => break Ok(false),
+
+                Action::CancelQuit => {
+                    app.current_screen = CurrentScreen::Main;
+                }
+
+                Action::ShowBackups => app.show_backups(),
+
+                Action::BackupPrevious => {
+                    app.selected_backup = app.selected_backup.saturating_sub(1);
                 }
 
-                Action::HpDecrease => {
-                    if app.char_sheet.health.current_hp >= 1 {
-                        app.char_sheet.health.current_hp = app.char_sheet.health.current_hp - 1;
-                    } else {
-                        app.char_sheet.health.current_hp = 0;
+                Action::BackupNext => {
+                    if app.selected_backup + 1 < app.backups.len() {
+                        app.selected_backup += 1;
                     }
                 }
 
+                Action::BackupRestore => {
+                    if let Err(err) = app.restore_selected_backup() {
+                        app.status = Some(format!("Restore failed: {err}"));
+                    }
+                }
+
+                Action::CloseBackups => {
+                    app.current_screen = CurrentScreen::Main;
+                }
+
+                Action::HpIncrease => app.change_hp(HpChangeKind::Healing, 1),
+
+                Action::HpDecrease => app.change_hp(HpChangeKind::Damage, 1),
+
+                Action::PromptHp(kind) => app.prompt_hp(kind),
+
+                Action::RollDeathSave => app.roll_death_save(),
+
+                Action::StartRolling => {
+                    app.current_screen = CurrentScreen::Rolling;
+                }
+
+                Action::MoveRollTarget(columns, rows) => {
+                    app.roll_target = offset_roll_target(app.roll_target, columns, rows);
+                }
+
+                Action::RollCheck(target, advantage) => app.roll_check(target, advantage),
+
+                Action::CycleAdvantage => app.cycle_advantage(),
+                Action::ScrollRollLog(step) => app.scroll_roll_log(step),
+
+                Action::ShowRollStats => app.show_roll_stats(),
+
+                Action::RollStatsPrevious => {
+                    app.selected_roll_session = app.selected_roll_session.saturating_sub(1);
+                }
+
+                Action::RollStatsNext => {
+                    // The first entry is every session together.
+                    if app.selected_roll_session < app.roll_sessions.len() {
+                        app.selected_roll_session += 1;
+                    }
+                }
+
+                Action::CloseRollStats => {
+                    app.current_screen = CurrentScreen::Rolling;
+                }
+
+                Action::ShowSpells => app.show_spells(),
+
+                Action::MoveSpell(step) => app.move_spell(step),
+
+                Action::ShiftCastSlot(step) => app.shift_cast_slot(step),
+
+                Action::CastSpell => app.cast_selected_spell(),
+
+                Action::TogglePrepared => app.toggle_prepared(),
+
+                Action::TogglePreparation => app.toggle_preparation(),
+
+                Action::RegainSlot => app.regain_slot(),
+
+                Action::AdjustMaxSlots(step) => app.adjust_max_slots(step),
+
+                Action::AdjustPactLevel(step) => app.adjust_pact_level(step),
+
+                Action::CycleCastingAbility => app.cycle_casting_ability(),
+
+                Action::ShowResources => app.show_resources(),
+
+                Action::MoveResource(step) => app.move_resource(step),
+
+                Action::SpendResource(index) => app.spend_resource(index),
+
+                Action::RestoreResource(index) => app.restore_resource(index),
+
+                Action::RefillResource => app.refill_resource(),
+
+                Action::ShowInventory => app.show_inventory(),
+
+                Action::MoveItemSelection(step) => app.move_item_selection(step),
+
+                Action::PromptItem => app.prompt_item(),
+
+                Action::AdjustItemQuantity(step) => app.adjust_item_quantity(step),
+
+                Action::RemoveItem => app.remove_item(),
+
+                Action::MoveItem => app.move_item(),
+
+                Action::ToggleEquipped => app.toggle_equipped(),
+
+                Action::ToggleAttuned => app.toggle_attuned(),
+
+                Action::ToggleContainer => app.toggle_container(),
+
+                Action::ToggleVariantEncumbrance => app.toggle_variant_encumbrance(),
+
+                Action::ShowWallet => app.show_wallet(),
+
+                Action::PromptMoney(kind) => app.prompt_money(kind),
+
+                Action::ConsolidateCoins => app.consolidate_coins(),
+
+                Action::ScrollLedger(step) => app.scroll_ledger(step),
+
+                Action::StartRest(kind) => app.start_rest(kind),
+
+                Action::SpendHitDie => app.spend_hit_die(),
+
+                Action::UndoHitDie => app.undo_hit_die(),
+
+                Action::FinishRest => app.finish_rest(),
+
+                Action::CancelRest => app.cancel_rest(),
+
+                Action::RecordDeathSave(success) => app.record_death_save(success),
+
+                Action::PromptPath(action) => {
+                    app.path_prompt = Some(PathPrompt {
+                        action,
+                        input: TextInput::default(),
+                    });
+                }
+
+                Action::StartEditing => {
+                    app.current_screen = CurrentScreen::Editing;
+                }
+
+                Action::StopEditing => {
+                    app.edit_input = None;
+                    app.current_screen = CurrentScreen::Main;
+                }
+
+                Action::SelectField(field) => {
+                    app.edit_field = field;
+                    app.edit_input = None;
+                    app.current_screen = CurrentScreen::Editing;
+                }
+
+                Action::MoveField(step) => {
+                    app.edit_field = app.edit_field.offset(step);
+                }
+
+                Action::EditField => app.begin_field_edit(),
+
+                Action::StartAbilityEditing => {
+                    app.current_screen = CurrentScreen::EditingAbilities;
+                }
+
+                Action::SelectAbility(ability) => {
+                    app.edit_ability = ability;
+                    app.edit_input = None;
+                    app.current_screen = CurrentScreen::EditingAbilities;
+                }
+
+                Action::MoveAbility(step) => {
+                    app.edit_ability = offset_ability(app.edit_ability, step);
+                }
+
+                Action::AdjustAbility(step) => app.adjust_ability(step),
+
+                Action::StartProficiencyEditing => {
+                    app.current_screen = CurrentScreen::EditingProficiencies;
+                }
+
+                Action::MoveProficiency(columns, rows) => {
+                    app.edit_proficiency = app.edit_proficiency.offset(columns, rows);
+                }
+
+                Action::ToggleProficiency(target) => {
+                    app.edit_proficiency = target;
+                    app.toggle_proficiency(target);
+                }
+
+                Action::InputChar(c) => {
+                    if let Some(input) = active_input(app) {
+                        input.insert(c);
+                    }
+                }
+
+                Action::InputBackspace => {
+                    if let Some(input) = active_input(app) {
+                        input.backspace();
+                    }
+                }
+
+                Action::InputDelete => {
+                    if let Some(input) = active_input(app) {
+                        input.delete();
+                    }
+                }
+
+                Action::InputLeft => {
+                    if let Some(input) = active_input(app) {
+                        input.move_left();
+                    }
+                }
+
+                Action::InputRight => {
+                    if let Some(input) = active_input(app) {
+                        input.move_right();
+                    }
+                }
+
+                Action::InputHome => {
+                    if let Some(input) = active_input(app) {
+                        input.move_home();
+                    }
+                }
+
+                Action::InputEnd => {
+                    if let Some(input) = active_input(app) {
+                        input.move_end();
+                    }
+                }
+
+                Action::InputSubmit => match app.current_screen {
+                    CurrentScreen::Editing | CurrentScreen::EditingAbilities => {
+                        app.commit_field_edit()
+                    }
+                    CurrentScreen::Main => app.commit_hp_prompt(),
+                    CurrentScreen::Inventory => app.commit_item_prompt(),
+                    CurrentScreen::Wallet => app.commit_money_prompt(),
+                    _ => {
+                        if let Some(prompt) = app.path_prompt.take() {
+                            let path = prompt.input.value().trim().to_string();
+                            if path.is_empty() {
+                                continue;
+                            }
+                            match prompt.action {
+                                PathAction::Open => app.open(path),
+                                PathAction::New => app.create(path),
+                            }
+                        }
+                    }
+                },
+
+                Action::InputCancel => {
+                    app.path_prompt = None;
+                    app.edit_input = None;
+                    app.hp_prompt = None;
+                    app.item_prompt = None;
+                    app.money_prompt = None;
+                }
+
                 Action::None => {}
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use char_sheet::resource::{Recharge, Resource};
+    use char_sheet::sheet::Skill;
+    use char_sheet::storage::Backup;
+    use ratatui::crossterm::event::KeyEvent;
+
+    // Only kept in memory: a new sheet isn't written until it is saved.
+    fn app(screen: CurrentScreen) -> App {
+        let Ok(Command::Run(options)) = cli::parse(["--new".into(), "unsaved.json".into()]) else {
+            panic!("the options were refused");
+        };
+        let mut app = App::new(options);
+        app.current_screen = screen;
+        app
+    }
+
+    // Strength's box at the top left, Athletics below it.
+    fn view_state() -> ViewState {
+        let mut view_state = ViewState::default();
+        view_state.stats.ability_rects = vec![(Ability::Strength, Rect::new(0, 0, 10, 4))];
+        view_state.stats.proficiency_rects = vec![(
+            ProficiencyTarget::Skill(Skill::Athletics),
+            Rect::new(0, 5, 20, 1),
+        )];
+        view_state
+    }
+
+    fn click(app: &App, button: MouseButton, modifiers: KeyModifiers, y: u16) -> Action {
+        let event = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Up(button),
+            column: 2,
+            row: y,
+            modifiers,
+        });
+        handle_event(event, app, &mut view_state())
+    }
+
+    #[test]
+    fn clicks_on_the_main_screen_roll() {
+        let app = app(CurrentScreen::Main);
+        let none = KeyModifiers::NONE;
+
+        assert!(matches!(
+            click(&app, MouseButton::Left, none, 1),
+            Action::RollCheck(RollTarget::Ability(Ability::Strength), None)
+        ));
+        assert!(matches!(
+            click(&app, MouseButton::Left, KeyModifiers::SHIFT, 5),
+            Action::RollCheck(
+                RollTarget::Skill(Skill::Athletics),
+                Some(Advantage::Advantage)
+            )
+        ));
+        assert!(matches!(
+            click(&app, MouseButton::Left, KeyModifiers::CONTROL, 5),
+            Action::RollCheck(_, Some(Advantage::Disadvantage))
+        ));
+        assert!(matches!(
+            click(&app, MouseButton::Right, none, 5),
+            Action::ToggleProficiency(ProficiencyTarget::Skill(Skill::Athletics))
+        ));
+        assert!(matches!(
+            click(&app, MouseButton::Left, none, 20),
+            Action::None
+        ));
+
+        let key = Event::Key(KeyEvent::from(KeyCode::Char('v')));
+        assert!(matches!(
+            handle_event(key, &app, &mut view_state()),
+            Action::CycleAdvantage
+        ));
+    }
+
+    #[test]
+    fn clicks_on_the_edit_screens_select_and_toggle() {
+        let app = app(CurrentScreen::EditingAbilities);
+        assert!(matches!(
+            click(&app, MouseButton::Left, KeyModifiers::NONE, 1),
+            Action::SelectAbility(Ability::Strength)
+        ));
+
+        let app = self::app(CurrentScreen::EditingProficiencies);
+        assert!(matches!(
+            click(&app, MouseButton::Left, KeyModifiers::NONE, 5),
+            Action::ToggleProficiency(ProficiencyTarget::Skill(Skill::Athletics))
+        ));
+    }
+
+    #[test]
+    fn a_set_up_advantage_is_used_once() {
+        let mut app = app(CurrentScreen::Main);
+        // Keeps the rolls out of a history file.
+        app.read_only = true;
+        app.cycle_advantage();
+        app.roll_check(RollTarget::Ability(Ability::Strength), None);
+        app.roll_check(RollTarget::Ability(Ability::Strength), None);
+
+        let advantages: Vec<_> = app
+            .roll_log
+            .iter()
+            .map(|entry| entry.roll.advantage)
+            .collect();
+        assert_eq!(advantages, [Some(Advantage::Advantage), None]);
+    }
+
+    #[test]
+    fn lowering_an_ability_takes_spent_resources_down_with_it() {
+        let mut app = app(CurrentScreen::Editing);
+        app.char_sheet.statistics.charisma = 16;
+        app.char_sheet.resources.push(Resource {
+            name: "Bardic Inspiration".into(),
+            max: 1,
+            current: 3,
+            recharge: Recharge::LongRest,
+            max_formula: Some("@cha".into()),
+        });
+        app.spend_resource(0);
+        assert_eq!(app.char_sheet.resources[0].current, 2);
+
+        // Charisma 12 only gives one use.
+        app.edit_ability = Ability::Charisma;
+        app.adjust_ability(-4);
+        assert_eq!(app.char_sheet.resources[0].current, 1);
+        assert!(app.char_sheet.validate().is_empty());
+    }
+
+    #[test]
+    fn restoring_a_backup_waits_for_unsaved_changes() {
+        let mut app = app(CurrentScreen::Backups);
+        app.char_sheet.information.character_name = "Unsaved".into();
+        app.dirty = true;
+        app.backups = vec![Backup {
+            index: 1,
+            path: "missing.json".into(),
+            saved: None,
+        }];
+
+        assert!(app.restore_selected_backup().is_ok());
+        assert_eq!(app.char_sheet.information.character_name, "Unsaved");
+        assert!(matches!(app.current_screen, CurrentScreen::Backups));
+        assert!(app
+            .status
+            .is_some_and(|status| status.starts_with("Save the changes")));
+    }
+
+    #[test]
+    fn only_real_changes_mark_the_sheet_unsaved() {
+        let mut app = app(CurrentScreen::Wallet);
+        // As if the new sheet had just been saved.
+        app.dirty = false;
+        app.modify(|sheet| {
+            sheet.wallet.pay(100, "a horse").ok();
+        });
+        assert!(
+            !app.dirty,
+            "a payment that can't be covered changes nothing"
+        );
+
+        app.edit_ability = Ability::Strength;
+        app.adjust_ability(1);
+        assert!(app.dirty);
+    }
+}
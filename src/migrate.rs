@@ -0,0 +1,374 @@
+// Upgrades sheets written by older builds. Every load runs the steps from
+// the file's `schema_version` up to `CURRENT_SCHEMA_VERSION`, and the sheet
+// is saved back at the current version.
+//
+// Migrations only go forward. A file from a newer build is refused with
+// `MigrationError::TooNew` rather than downgraded: going back would mean
+// dropping whatever the newer version added (spellcasting, say) and the
+// next save would lose it for good. To go back to an older build, restore
+// one of the backups written before the upgrade instead.
+
+use serde_json::{Map, Value};
+use std::fmt;
+
+use crate::rules;
+
+/// The schema version written by this build. Bump it whenever `CharSheet`
+/// changes shape and append the matching step to `MIGRATIONS`.
+pub const CURRENT_SCHEMA_VERSION: u32 = 5;
+
+/// Files written before versioning was introduced carry no
+/// `schema_version` field and are treated as version 1.
+const UNVERSIONED: u32 = 1;
+
+type Migration = fn(&mut Value);
+
+// `MIGRATIONS[n]` upgrades a sheet from version `n + 1` to `n + 2`.
+const MIGRATIONS: [Migration; (CURRENT_SCHEMA_VERSION - UNVERSIONED) as usize] = [
+    upgrade_legacy,
+    derive_computed_stats,
+    type_death_saves,
+    add_character_options,
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    NotAnObject,
+    InvalidVersion(Value),
+    TooNew { found: u32, supported: u32 },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::NotAnObject => write!(f, "the sheet is not a JSON object"),
+            MigrationError::InvalidVersion(value) => {
+                write!(
+                    f,
+                    "\"schema_version\" must be a positive integer, found {value}"
+                )
+            }
+            MigrationError::TooNew { found, supported } => write!(
+                f,
+                "the sheet uses schema version {found} but this build only understands \
+                 up to version {supported}; please upgrade char-sheet"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// Brings a freshly parsed sheet up to `CURRENT_SCHEMA_VERSION`, running
+/// every migration step between the file's version and ours in order.
+pub fn migrate(sheet: &mut Value) -> Result<(), MigrationError> {
+    let version = schema_version(sheet)?;
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(MigrationError::TooNew {
+            found: version,
+            supported: CURRENT_SCHEMA_VERSION,
+        });
+    }
+
+    for step in &MIGRATIONS[(version - UNVERSIONED) as usize..] {
+        step(sheet);
+    }
+
+    sheet
+        .as_object_mut()
+        .ok_or(MigrationError::NotAnObject)?
+        .insert("schema_version".into(), CURRENT_SCHEMA_VERSION.into());
+    Ok(())
+}
+
+fn schema_version(sheet: &Value) -> Result<u32, MigrationError> {
+    let object = sheet.as_object().ok_or(MigrationError::NotAnObject)?;
+    match object.get("schema_version") {
+        None => Ok(UNVERSIONED),
+        Some(value) => value
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= UNVERSIONED)
+            .ok_or_else(|| MigrationError::InvalidVersion(value.clone())),
+    }
+}
+
+/// v1 -> v2: rewrites a sheet saved in the original flat format, where
+/// saving throws were six `*_proficent` bools and skills were 36 string
+/// fields, into the map-based shape understood by `CharSheet`. Unversioned
+/// sheets that already use the map shape are left untouched.
+fn upgrade_legacy(sheet: &mut Value) {
+    if let Some(saving_throws) = sheet.get_mut("saving_throws") {
+        upgrade_saving_throws(saving_throws);
+    }
+    if let Some(skills) = sheet.get_mut("skills") {
+        upgrade_skills(skills);
+    }
+}
+
+fn upgrade_saving_throws(saving_throws: &mut Value) {
+    let Some(old) = saving_throws.as_object() else {
+        return;
+    };
+    if !old.keys().any(|key| key.ends_with("_proficent")) {
+        return;
+    }
+
+    let mut new = Map::new();
+    for (key, proficient) in old {
+        if let Some(ability) = key.strip_suffix("_proficent") {
+            new.insert(ability.to_string(), proficient.clone());
+        }
+    }
+    *saving_throws = Value::Object(new);
+}
+
+fn upgrade_skills(skills: &mut Value) {
+    let Some(old) = skills.as_object() else {
+        return;
+    };
+    if !old.keys().any(|key| key.ends_with("_skill")) {
+        return;
+    }
+
+    let mut new = Map::new();
+    for (key, value) in old {
+        // The old hand-typed totals (`"acrobatics": "+2"`) are dropped on
+        // purpose: totals are now derived from the ability scores.
+        if let Some(name) = key.strip_suffix("_skill") {
+            let proficiency = match value.as_str() {
+                Some("proficient") => "proficient",
+                Some("expertise") => "expertise",
+                _ => "none",
+            };
+            skill_entry(&mut new, name).insert("proficiency".into(), proficiency.into());
+        } else if let Some(name) = key.strip_suffix("_override")
+            && !value.is_null()
+        {
+            skill_entry(&mut new, name).insert("override".into(), value.clone());
+        }
+    }
+    *skills = Value::Object(new);
+}
+
+fn skill_entry<'a>(
+    skills: &'a mut Map<String, Value>,
+    legacy_name: &str,
+) -> &'a mut Map<String, Value> {
+    // "Sleight of Hand" was misspelled in the original field names.
+    let name = match legacy_name {
+        "slight_of_hand" => "sleight_of_hand",
+        other => other,
+    };
+
+    skills
+        .entry(name)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .expect("skill entries are always objects")
+}
+
+/// v2 -> v3: proficiency bonus, passive perception and initiative became
+/// rules-derived values. A hand-entered value only survives as an override
+/// when it disagrees with what the rules would compute for the sheet.
+fn derive_computed_stats(sheet: &mut Value) {
+    let level = u8_at(sheet, "/information/level").unwrap_or(1);
+    let dexterity = u8_at(sheet, "/statistics/dexterity").unwrap_or(10);
+    let wisdom = u8_at(sheet, "/statistics/wisdom").unwrap_or(10);
+
+    let old_prof_bonus = u8_at(sheet, "/statistics/proficiency_bonus");
+    let prof_bonus = rules::proficiency_bonus(level);
+    let prof_bonus_override = old_prof_bonus.filter(|old| *old != prof_bonus);
+    let effective_prof_bonus = rules::signed_bonus(prof_bonus_override.unwrap_or(prof_bonus));
+
+    let perception = match sheet.pointer("/skills/perception/override") {
+        Some(total) => total
+            .as_i64()
+            .map_or(0, |total| total.clamp(i8::MIN.into(), i8::MAX.into()) as i8),
+        None => {
+            let proficiency = sheet
+                .pointer("/skills/perception/proficiency")
+                .and_then(Value::as_str);
+            rules::ability_mod(wisdom).saturating_add(match proficiency {
+                Some("proficient") => effective_prof_bonus,
+                Some("expertise") => effective_prof_bonus.saturating_mul(2),
+                _ => 0,
+            })
+        }
+    };
+    let passive_perception_override = u8_at(sheet, "/statistics/passive_wisdom_perception")
+        .filter(|old| *old != rules::passive_score(perception));
+
+    let initiative_override = sheet
+        .pointer("/health/initiative")
+        .and_then(Value::as_str)
+        .and_then(|old| old.trim().parse::<i8>().ok())
+        .filter(|old| *old != rules::initiative(dexterity));
+
+    if let Some(statistics) = sheet.get_mut("statistics").and_then(Value::as_object_mut) {
+        statistics.remove("proficiency_bonus");
+        statistics.remove("passive_wisdom_perception");
+        if let Some(value) = prof_bonus_override {
+            statistics.insert("proficiency_bonus_override".into(), value.into());
+        }
+        if let Some(value) = passive_perception_override {
+            statistics.insert("passive_perception_override".into(), value.into());
+        }
+    }
+    if let Some(health) = sheet.get_mut("health").and_then(Value::as_object_mut) {
+        health.remove("initiative");
+        if let Some(value) = initiative_override {
+            health.insert("initiative_override".into(), value.into());
+        }
+    }
+}
+
+/// v3 -> v4: the `"0/3"` death save strings became counters and the
+/// misspelt `unconcicious` flag was renamed.
+fn type_death_saves(sheet: &mut Value) {
+    let Some(health) = sheet.get_mut("health").and_then(Value::as_object_mut) else {
+        return;
+    };
+
+    let count = |value: Option<Value>| -> u8 {
+        value
+            .as_ref()
+            .and_then(Value::as_str)
+            .and_then(|text| text.split('/').next())
+            .and_then(|count| count.trim().parse::<u8>().ok())
+            .unwrap_or(0)
+            .min(rules::DEATH_SAVES_NEEDED)
+    };
+    let successes = count(health.remove("death_save_saves"));
+    let failures = count(health.remove("death_save_failes"));
+    health.insert(
+        "death_saves".into(),
+        serde_json::json!({ "successes": successes, "failures": failures }),
+    );
+
+    if let Some(unconscious) = health.remove("unconcicious") {
+        health.insert("unconscious".into(), unconscious);
+    }
+}
+
+/// v4 -> v5: spellcasting, resources, the inventory and the wallet were
+/// added. They are all optional, so there is nothing to change; the bump
+/// makes older builds refuse these sheets rather than drop them on save.
+fn add_character_options(_sheet: &mut Value) {}
+
+fn u8_at(sheet: &Value, pointer: &str) -> Option<u8> {
+    sheet
+        .pointer(pointer)
+        .and_then(Value::as_u64)
+        .and_then(|value| u8::try_from(value).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn stamps_the_current_version() {
+        let mut sheet = json!({});
+        migrate(&mut sheet).unwrap();
+        assert_eq!(sheet["schema_version"], CURRENT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn only_runs_the_steps_a_sheet_needs() {
+        // At version 3 the computed stats were already derived, so a stray
+        // `proficiency_bonus` is left for serde to ignore.
+        let mut sheet = json!({
+            "schema_version": 3,
+            "statistics": { "proficiency_bonus": 3 },
+            "health": { "death_save_saves": "2/3" },
+        });
+        migrate(&mut sheet).unwrap();
+
+        assert_eq!(sheet["statistics"]["proficiency_bonus"], 3);
+        assert_eq!(sheet["health"]["death_saves"]["successes"], 2);
+    }
+
+    #[test]
+    fn keeps_computed_stats_only_when_they_disagree() {
+        let mut sheet = json!({
+            "schema_version": 2,
+            "information": { "level": 5 },
+            "statistics": {
+                "dexterity": 14,
+                "wisdom": 12,
+                "proficiency_bonus": 4,
+                "passive_wisdom_perception": 15,
+            },
+            "skills": { "perception": { "proficiency": "proficient" } },
+            "health": { "initiative": "+2" },
+        });
+        migrate(&mut sheet).unwrap();
+
+        // Level 5 gives +3, so the +4 was set by hand, and perception with
+        // it comes to 1 + 4 for a passive score of 15.
+        let statistics = &sheet["statistics"];
+        assert_eq!(statistics["proficiency_bonus_override"], 4);
+        assert!(statistics.get("proficiency_bonus").is_none());
+        assert!(statistics.get("passive_perception_override").is_none());
+        assert!(statistics.get("passive_wisdom_perception").is_none());
+        assert!(sheet["health"].get("initiative_override").is_none());
+        assert!(sheet["health"].get("initiative").is_none());
+    }
+
+    #[test]
+    fn types_death_saves_and_renames_unconscious() {
+        let mut sheet = json!({
+            "schema_version": 3,
+            "health": {
+                "unconcicious": true,
+                "death_save_saves": "1/3",
+                "death_save_failes": " 2 / 3",
+            },
+        });
+        migrate(&mut sheet).unwrap();
+
+        assert_eq!(
+            sheet["health"],
+            json!({
+                "unconscious": true,
+                "death_saves": { "successes": 1, "failures": 2 },
+            })
+        );
+    }
+
+    #[test]
+    fn version_4_sheets_load_unchanged() {
+        let original = json!({
+            "schema_version": 4,
+            "health": { "death_saves": { "successes": 1, "failures": 0 } },
+        });
+        let mut sheet = original.clone();
+        migrate(&mut sheet).unwrap();
+
+        assert_eq!(sheet["health"], original["health"]);
+        assert_eq!(sheet["schema_version"], 5);
+    }
+
+    #[test]
+    fn refuses_newer_and_invalid_versions() {
+        let mut newer = json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1 });
+        assert!(matches!(
+            migrate(&mut newer),
+            Err(MigrationError::TooNew { found, .. }) if found == CURRENT_SCHEMA_VERSION + 1
+        ));
+
+        for version in [json!(0), json!(-1), json!("4"), json!(1.5)] {
+            let mut sheet = json!({ "schema_version": version });
+            assert!(matches!(
+                migrate(&mut sheet),
+                Err(MigrationError::InvalidVersion(_))
+            ));
+        }
+        assert!(matches!(
+            migrate(&mut json!([])),
+            Err(MigrationError::NotAnObject)
+        ));
+    }
+}
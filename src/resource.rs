@@ -0,0 +1,177 @@
+// Limited-use class features such as rage, ki points, sorcery points or
+// bardic inspiration. Each one is a counter with a maximum that comes back
+// on a rest.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dice;
+use crate::rest::RestKind;
+use crate::sheet::CharSheet;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Resource {
+    pub name: String,
+    /// Used when there is no `max_formula`, or the formula can't be worked
+    /// out.
+    pub max: u8,
+    pub current: u8,
+    pub recharge: Recharge,
+    /// Works the maximum out from the sheet, e.g. `@level` for ki points or
+    /// `@cha` for bardic inspiration. Uses the same `@` references as dice
+    /// expressions, but can't roll dice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_formula: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recharge {
+    ShortRest,
+    LongRest,
+    /// The sheet doesn't track the time of day, so a long rest is taken to
+    /// last through the dawn.
+    Dawn,
+}
+
+impl Recharge {
+    pub fn label(self) -> &'static str {
+        match self {
+            Recharge::ShortRest => "short rest",
+            Recharge::LongRest => "long rest",
+            Recharge::Dawn => "dawn",
+        }
+    }
+
+    /// Whether a rest of `kind` brings the resource back.
+    pub fn on(self, kind: RestKind) -> bool {
+        match self {
+            Recharge::ShortRest => true,
+            Recharge::LongRest | Recharge::Dawn => kind == RestKind::Long,
+        }
+    }
+}
+
+impl Resource {
+    /// The maximum for `sheet`: the formula when there is one, the stored
+    /// `max` otherwise. A formula below 0 counts as 0. This is the `max` that
+    /// `restore` and `refill` expect.
+    pub fn max(&self, sheet: &CharSheet) -> u8 {
+        self.max_formula
+            .as_deref()
+            .and_then(|formula| dice::evaluate(formula, sheet).ok())
+            .map_or(self.max, |max| max.clamp(0, i32::from(u8::MAX)) as u8)
+    }
+
+    /// Uses one. Returns whether there was one left to use.
+    pub fn spend(&mut self) -> bool {
+        if self.current == 0 {
+            return false;
+        }
+        self.current -= 1;
+        true
+    }
+
+    /// Gets one back, up to `max`. Returns whether one was missing.
+    pub fn restore(&mut self, max: u8) -> bool {
+        if self.current >= max {
+            return false;
+        }
+        self.current += 1;
+        true
+    }
+
+    /// Fills the resource up to `max`, returning how many uses came back.
+    pub fn refill(&mut self, max: u8) -> u8 {
+        let regained = max.saturating_sub(self.current);
+        self.current = max;
+        regained
+    }
+
+    pub fn validate(&self, sheet: &CharSheet, problems: &mut Vec<String>) {
+        if let Some(formula) = &self.max_formula
+            && let Err(err) = dice::evaluate(formula, sheet)
+        {
+            problems.push(format!(
+                "the maximum of {} can't be worked out from '{formula}': {err}",
+                self.name
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(current: u8, max_formula: Option<&str>) -> Resource {
+        Resource {
+            name: "Ki".into(),
+            max: 2,
+            current,
+            recharge: Recharge::ShortRest,
+            max_formula: max_formula.map(Into::into),
+        }
+    }
+
+    fn level(level: u8) -> CharSheet {
+        let mut sheet = CharSheet::blank();
+        sheet.information.level = level;
+        sheet
+    }
+
+    #[test]
+    fn the_maximum_comes_from_the_formula_when_it_works() {
+        let sheet = level(5);
+        assert_eq!(resource(0, None).max(&sheet), 2);
+        assert_eq!(resource(0, Some("@level")).max(&sheet), 5);
+        assert_eq!(resource(0, Some("@level-10")).max(&sheet), 0);
+        assert_eq!(resource(0, Some("@level*100")).max(&sheet), u8::MAX);
+        // A formula that can't be worked out falls back to `max`.
+        assert_eq!(resource(0, Some("1d6")).max(&sheet), 2);
+        assert_eq!(resource(0, Some("@nonsense")).max(&sheet), 2);
+    }
+
+    #[test]
+    fn spending_and_restoring_stop_at_the_ends() {
+        let mut ki = resource(1, None);
+        assert!(ki.spend());
+        assert!(!ki.spend());
+        assert_eq!(ki.current, 0);
+
+        assert!(ki.restore(1));
+        assert!(!ki.restore(1));
+        assert_eq!(ki.current, 1);
+    }
+
+    #[test]
+    fn refilling_reports_what_came_back() {
+        let mut ki = resource(1, None);
+        assert_eq!(ki.refill(4), 3);
+        assert_eq!(ki.current, 4);
+        assert_eq!(ki.refill(4), 0);
+    }
+
+    #[test]
+    fn dawn_comes_with_a_long_rest() {
+        assert!(Recharge::ShortRest.on(RestKind::Short));
+        assert!(Recharge::ShortRest.on(RestKind::Long));
+        assert!(!Recharge::LongRest.on(RestKind::Short));
+        assert!(Recharge::LongRest.on(RestKind::Long));
+        assert!(!Recharge::Dawn.on(RestKind::Short));
+        assert!(Recharge::Dawn.on(RestKind::Long));
+    }
+
+    #[test]
+    fn validation_catches_formulas_that_cant_be_worked_out() {
+        let sheet = level(3);
+        let mut problems = Vec::new();
+        resource(9, Some("@level")).validate(&sheet, &mut problems);
+        assert!(problems.is_empty(), "{problems:?}");
+
+        resource(0, Some("2d4")).validate(&sheet, &mut problems);
+        assert_eq!(
+            problems,
+            ["the maximum of Ki can't be worked out from '2d4': a formula can't roll dice"]
+        );
+    }
+}
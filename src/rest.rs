@@ -0,0 +1,156 @@
+// Short and long rests. A rest is worked out as a `RestSummary` first so the
+// player can see what it will restore, and only written to the sheet once
+// they confirm it.
+
+use crate::rules;
+use crate::sheet::{Ability, CharSheet, DeathSaves};
+use crate::spells::Spellcasting;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestKind {
+    Short,
+    Long,
+}
+
+impl RestKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            RestKind::Short => "Short rest",
+            RestKind::Long => "Long rest",
+        }
+    }
+}
+
+/// A rest in progress. During a short rest the player spends hit dice one at
+/// a time; `hit_die_rolls` holds the face each one came up.
+#[derive(Clone, Debug)]
+pub struct Rest {
+    pub kind: RestKind,
+    pub hit_die_rolls: Vec<u32>,
+}
+
+/// What finishing a rest would change.
+#[derive(Clone, Debug)]
+pub struct RestSummary {
+    pub hp_before: u8,
+    pub hp_after: u8,
+    pub hit_dice_before: u8,
+    pub hit_dice_after: u8,
+    /// HP regained from each hit die spent, Constitution included.
+    pub hit_die_healing: Vec<u32>,
+    pub death_saves_reset: bool,
+    pub spell_slots_regained: u16,
+    /// Pact slots come back on short rests as well as long ones.
+    pub pact_slots_regained: u8,
+    /// Resources that recharge on this rest and had uses missing, with how
+    /// many come back.
+    pub resources_recharged: Vec<(String, u8)>,
+}
+
+impl Rest {
+    pub fn new(kind: RestKind) -> Rest {
+        Rest {
+            kind,
+            hit_die_rolls: Vec::new(),
+        }
+    }
+
+    /// Why the character can't take this rest, if they can't.
+    pub fn check(&self, sheet: &CharSheet) -> Result<(), String> {
+        if sheet.health.current_hp == 0 {
+            return Err("A character needs at least 1 HP to rest".into());
+        }
+        Ok(())
+    }
+
+    /// Whether another hit die can be spent on this rest. Only a single die
+    /// such as d8 can be rolled; a multiclass character with mixed hit dice
+    /// still gets everything else a short rest brings back.
+    pub fn can_spend_hit_die(&self, sheet: &CharSheet) -> bool {
+        self.kind == RestKind::Short
+            && sheet.health.hit_die_sides().is_some()
+            && self.hit_die_rolls.len() < usize::from(sheet.health.current_hit_dice)
+    }
+
+    pub fn summary(&self, sheet: &CharSheet) -> RestSummary {
+        let health = &sheet.health;
+        let mut summary = RestSummary {
+            hp_before: health.current_hp,
+            hp_after: health.current_hp,
+            hit_dice_before: health.current_hit_dice,
+            hit_dice_after: health.current_hit_dice,
+            hit_die_healing: Vec::new(),
+            death_saves_reset: false,
+            spell_slots_regained: 0,
+            pact_slots_regained: sheet
+                .spellcasting
+                .as_ref()
+                .map_or(0, Spellcasting::expended_pact_slots),
+            resources_recharged: sheet
+                .resources
+                .iter()
+                .filter(|resource| resource.recharge.on(self.kind))
+                .filter_map(|resource| {
+                    let missing = resource.max(sheet).saturating_sub(resource.current);
+                    (missing > 0).then(|| (resource.name.clone(), missing))
+                })
+                .collect(),
+        };
+
+        match self.kind {
+            RestKind::Short => {
+                let constitution = sheet.statistics.modifier(Ability::Constitution);
+                summary.hit_die_healing = self
+                    .hit_die_rolls
+                    .iter()
+                    .map(|die| rules::hit_die_healing(*die, constitution))
+                    .collect();
+                let healed: u32 = summary.hit_die_healing.iter().sum();
+                summary.hp_after =
+                    (u32::from(health.current_hp) + healed).min(u32::from(health.maximum_hp)) as u8;
+                summary.hit_dice_after = health
+                    .current_hit_dice
+                    .saturating_sub(self.hit_die_rolls.len() as u8);
+            }
+            RestKind::Long => {
+                summary.hp_after = health.maximum_hp;
+                summary.hit_dice_after = health
+                    .current_hit_dice
+                    .saturating_add(rules::hit_dice_regained(health.total_hit_dice))
+                    .min(health.total_hit_dice);
+                summary.death_saves_reset =
+                    health.death_saves.successes > 0 || health.death_saves.failures > 0;
+                summary.spell_slots_regained = sheet
+                    .spellcasting
+                    .as_ref()
+                    .map_or(0, Spellcasting::expended_slots);
+            }
+        }
+        summary
+    }
+
+    /// Writes the rest to the sheet, returning what it changed.
+    pub fn apply(&self, sheet: &mut CharSheet) -> RestSummary {
+        let summary = self.summary(sheet);
+        if let Some(spellcasting) = &mut sheet.spellcasting {
+            spellcasting.regain_pact_slots();
+        }
+        let maxima: Vec<u8> = sheet.resources.iter().map(|r| r.max(sheet)).collect();
+        for (resource, max) in sheet.resources.iter_mut().zip(maxima) {
+            if resource.recharge.on(self.kind) {
+                resource.refill(max);
+            }
+        }
+        let health = &mut sheet.health;
+        health.current_hp = summary.hp_after;
+        health.current_hit_dice = summary.hit_dice_after;
+        if self.kind == RestKind::Long {
+            health.death_saves = DeathSaves::default();
+            health.unconscious = false;
+            if let Some(spellcasting) = &mut sheet.spellcasting {
+                spellcasting.regain_all_slots();
+            }
+        }
+        summary
+    }
+}
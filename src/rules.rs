@@ -0,0 +1,121 @@
+// Game rules that turn the numbers stored on a sheet into the numbers shown
+// on it. Everything in here is a pure function of its inputs.
+
+/// Base value of every passive check before the check modifier is added.
+pub const PASSIVE_CHECK_BASE: i8 = 10;
+
+/// Ability scores can't go below 1 or above 30.
+pub const MIN_ABILITY_SCORE: u8 = 1;
+pub const MAX_ABILITY_SCORE: u8 = 30;
+
+/// Spell levels run from 1 to 9; cantrips are level 0.
+pub const MAX_SPELL_LEVEL: u8 = 9;
+/// Pact magic slots never go above 5th level.
+pub const MAX_PACT_SLOT_LEVEL: u8 = 5;
+/// Base of the spell save DC before proficiency and the casting ability.
+pub const SPELL_SAVE_DC_BASE: i8 = 8;
+
+/// A character can carry 15 pounds per point of Strength. Under the variant
+/// encumbrance rules more than 5 pounds per point encumbers them and more
+/// than 10 heavily encumbers them.
+pub const CARRYING_CAPACITY_PER_STRENGTH: f64 = 15.0;
+pub const ENCUMBERED_PER_STRENGTH: f64 = 5.0;
+pub const HEAVILY_ENCUMBERED_PER_STRENGTH: f64 = 10.0;
+/// Feet of speed lost while encumbered and heavily encumbered.
+pub const ENCUMBERED_SPEED_PENALTY: u8 = 10;
+pub const HEAVILY_ENCUMBERED_SPEED_PENALTY: u8 = 20;
+/// No more than three magic items can be attuned at once.
+pub const MAX_ATTUNED_ITEMS: usize = 3;
+
+/// Hand-set bonuses can't go past this either way, which keeps every sum
+/// built on them well inside an `i8`.
+pub const MAX_BONUS_OVERRIDE: i8 = 30;
+
+/// A death saving throw of this or more on the d20 is a success.
+pub const DEATH_SAVE_DC: u32 = 10;
+/// Three successes stabilise a dying character; three failures kill them.
+pub const DEATH_SAVES_NEEDED: u8 = 3;
+
+pub fn ability_mod(stat: u8) -> i8 {
+    // Round towards negative infinity so that e.g. a score of 9 gives -1.
+    ((i16::from(stat) - 10).div_euclid(2)) as i8
+}
+
+/// `bonus` as a signed amount to add to a modifier.
+pub fn signed_bonus(bonus: u8) -> i8 {
+    i8::try_from(bonus).unwrap_or(i8::MAX)
+}
+
+/// +2 at levels 1-4, rising by one every four levels up to +6 at level 17.
+pub fn proficiency_bonus(level: u8) -> u8 {
+    2 + (level.clamp(1, 20) - 1) / 4
+}
+
+/// Initiative is a plain Dexterity check.
+pub fn initiative(dexterity: u8) -> i8 {
+    ability_mod(dexterity)
+}
+
+/// A passive score is 10 plus every modifier that applies to the check.
+pub fn passive_score(check_modifier: i8) -> u8 {
+    PASSIVE_CHECK_BASE.saturating_add(check_modifier).max(0) as u8
+}
+
+/// HP regained from one hit die spent on a short rest. A low roll with a
+/// Constitution penalty can't take HP away.
+pub fn hit_die_healing(die: u32, constitution_mod: i8) -> u32 {
+    (die as i32 + i32::from(constitution_mod)).max(0) as u32
+}
+
+/// A long rest gives back half the character's hit dice, at least one.
+pub fn hit_dice_regained(total_hit_dice: u8) -> u8 {
+    (total_hit_dice / 2).max(1)
+}
+
+/// The DC a target has to beat to resist the character's spells.
+pub fn spell_save_dc(proficiency_bonus: u8, casting_mod: i8) -> u8 {
+    SPELL_SAVE_DC_BASE
+        .saturating_add(signed_bonus(proficiency_bonus))
+        .saturating_add(casting_mod)
+        .max(0) as u8
+}
+
+/// Added to the d20 when making a spell attack.
+pub fn spell_attack_bonus(proficiency_bonus: u8, casting_mod: i8) -> i8 {
+    signed_bonus(proficiency_bonus).saturating_add(casting_mod)
+}
+
+/// Pounds the character can carry.
+pub fn carrying_capacity(strength: u8) -> f64 {
+    f64::from(strength) * CARRYING_CAPACITY_PER_STRENGTH
+}
+
+pub fn encumbered_above(strength: u8) -> f64 {
+    f64::from(strength) * ENCUMBERED_PER_STRENGTH
+}
+
+pub fn heavily_encumbered_above(strength: u8) -> f64 {
+    f64::from(strength) * HEAVILY_ENCUMBERED_PER_STRENGTH
+}
+
+/// A value computed by the rules unless the sheet pins it by hand.
+#[derive(Clone, Copy)]
+pub struct Derived<T> {
+    pub value: T,
+    pub overridden: bool,
+}
+
+impl<T> Derived<T> {
+    pub fn resolve(computed: T, manual: Option<T>) -> Derived<T> {
+        match manual {
+            Some(value) => Derived {
+                value,
+                overridden: true,
+            },
+            None => Derived {
+                value: computed,
+                overridden: false,
+            },
+        }
+    }
+}
@@ -0,0 +1,1021 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::inventory::{Encumbrance, Inventory};
+use crate::migrate::CURRENT_SCHEMA_VERSION;
+use crate::resource::Resource;
+use crate::rules::{self, Derived};
+use crate::spells::Spellcasting;
+use crate::wallet::Wallet;
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[allow(dead_code)]
+pub struct Information {
+    pub character_name: String,
+    pub class: String,
+    pub level: u8,
+    pub background: String,
+    pub player_name: String,
+    pub race: String,
+    pub alignment: String,
+    pub experience: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[allow(dead_code)]
+// Modifiers will be calculated based on rules of the game
+pub struct Statistics {
+    pub strength: u8,
+    pub dexterity: u8,
+    pub constitution: u8,
+    pub intelligence: u8,
+    pub wisdom: u8,
+    pub charisma: u8,
+    pub inspiration: bool,
+    // Derived from the character level unless set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proficiency_bonus_override: Option<u8>,
+    // Derived from Wisdom and the Perception skill unless set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passive_perception_override: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ability {
+    Strength,
+    Dexterity,
+    Constitution,
+    Intelligence,
+    Wisdom,
+    Charisma,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 6] = [
+        Ability::Strength,
+        Ability::Dexterity,
+        Ability::Constitution,
+        Ability::Intelligence,
+        Ability::Wisdom,
+        Ability::Charisma,
+    ];
+
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            Ability::Strength => "STR",
+            Ability::Dexterity => "DEX",
+            Ability::Constitution => "CON",
+            Ability::Intelligence => "INT",
+            Ability::Wisdom => "WIS",
+            Ability::Charisma => "CHA",
+        }
+    }
+
+    /// Looks an ability up by its full name or abbreviation, ignoring case.
+    pub fn from_name(name: &str) -> Option<Ability> {
+        Ability::ALL.into_iter().find(|ability| {
+            name.eq_ignore_ascii_case(ability.abbreviation())
+                || name.eq_ignore_ascii_case(ability.key())
+        })
+    }
+
+    /// The name used for this ability in the sheet file.
+    pub fn key(self) -> &'static str {
+        match self {
+            Ability::Strength => "strength",
+            Ability::Dexterity => "dexterity",
+            Ability::Constitution => "constitution",
+            Ability::Intelligence => "intelligence",
+            Ability::Wisdom => "wisdom",
+            Ability::Charisma => "charisma",
+        }
+    }
+
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Ability::Strength => "Str",
+            Ability::Dexterity => "Dex",
+            Ability::Constitution => "Con",
+            Ability::Intelligence => "Int",
+            Ability::Wisdom => "Wis",
+            Ability::Charisma => "Cha",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Skill {
+    Acrobatics,
+    AnimalHandling,
+    Arcana,
+    Athletics,
+    Deception,
+    History,
+    Insight,
+    Intimidation,
+    Investigation,
+    Medicine,
+    Nature,
+    Perception,
+    Performance,
+    Persuasion,
+    Religion,
+    SleightOfHand,
+    Stealth,
+    Survival,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 18] = [
+        Skill::Acrobatics,
+        Skill::AnimalHandling,
+        Skill::Arcana,
+        Skill::Athletics,
+        Skill::Deception,
+        Skill::History,
+        Skill::Insight,
+        Skill::Intimidation,
+        Skill::Investigation,
+        Skill::Medicine,
+        Skill::Nature,
+        Skill::Perception,
+        Skill::Performance,
+        Skill::Persuasion,
+        Skill::Religion,
+        Skill::SleightOfHand,
+        Skill::Stealth,
+        Skill::Survival,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Skill::Acrobatics => "Acrobatics",
+            Skill::AnimalHandling => "Animal Handling",
+            Skill::Arcana => "Arcana",
+            Skill::Athletics => "Athletics",
+            Skill::Deception => "Deception",
+            Skill::History => "History",
+            Skill::Insight => "Insight",
+            Skill::Intimidation => "Intimidation",
+            Skill::Investigation => "Investigation",
+            Skill::Medicine => "Medicine",
+            Skill::Nature => "Nature",
+            Skill::Perception => "Perception",
+            Skill::Performance => "Performance",
+            Skill::Persuasion => "Persuasion",
+            Skill::Religion => "Religion",
+            Skill::SleightOfHand => "Sleight of Hand",
+            Skill::Stealth => "Stealth",
+            Skill::Survival => "Survival",
+        }
+    }
+
+    /// Looks a skill up by its display name or its name in the sheet file
+    /// (`"Sleight of Hand"` or `"sleight_of_hand"`), ignoring case.
+    pub fn from_name(name: &str) -> Option<Skill> {
+        let name = name.replace(['_', '-'], " ");
+        Skill::ALL
+            .into_iter()
+            .find(|skill| name.eq_ignore_ascii_case(skill.name()))
+    }
+
+    /// The ability whose modifier this skill is rolled with.
+    pub fn ability(self) -> Ability {
+        match self {
+            Skill::Athletics => Ability::Strength,
+            Skill::Acrobatics | Skill::SleightOfHand | Skill::Stealth => Ability::Dexterity,
+            Skill::Arcana
+            | Skill::History
+            | Skill::Investigation
+            | Skill::Nature
+            | Skill::Religion => Ability::Intelligence,
+            Skill::AnimalHandling
+            | Skill::Insight
+            | Skill::Medicine
+            | Skill::Perception
+            | Skill::Survival => Ability::Wisdom,
+            Skill::Deception | Skill::Intimidation | Skill::Performance | Skill::Persuasion => {
+                Ability::Charisma
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct SavingThrows {
+    pub proficiencies: BTreeMap<Ability, bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct SkillEntry {
+    #[serde(default)]
+    pub proficiency: SkillProficiency,
+    // Pins the total by hand instead of deriving it from the ability.
+    #[serde(default, rename = "override", skip_serializing_if = "Option::is_none")]
+    pub total_override: Option<i8>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+// Skill totals are derived from the governing ability, the proficiency bonus
+// and the proficiency level unless an entry carries an override.
+pub struct Skills {
+    pub entries: BTreeMap<Skill, SkillEntry>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[allow(dead_code)]
+pub struct ProficienciesAndLanguage {
+    pub languages_known: String,
+    pub armor_proficiency: String,
+    pub weapon_proficiency: String,
+    pub tools_proficiency: String,
+}
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[allow(dead_code)]
+pub struct Health {
+    pub armor_class: u8,
+    // Derived from Dexterity unless set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initiative_override: Option<i8>,
+    pub speed: u8,
+    pub current_hp: u8,
+    pub maximum_hp: u8,
+    pub temporary_hp: u8,
+    pub hit_dice_type: String,
+    pub total_hit_dice: u8,
+    pub current_hit_dice: u8,
+    // Set when HP drop to 0 and cleared by any healing.
+    #[serde(default)]
+    pub unconscious: bool,
+    #[serde(default)]
+    pub death_saves: DeathSaves,
+}
+
+/// Death saving throws made since the character last dropped to 0 HP.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct DeathSaves {
+    pub successes: u8,
+    pub failures: u8,
+}
+
+/// Where a character stands on the road between fighting fit and dead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifeState {
+    Conscious,
+    Dying,
+    Stable,
+    Dead,
+}
+
+/// The result of one death saving throw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathSaveOutcome {
+    Success,
+    Failure,
+    /// A natural 1 counts as two failures.
+    DoubleFailure,
+    /// A natural 20 brings the character back with 1 HP.
+    Revived,
+    Stabilized,
+    Died,
+}
+
+/// What a call to [`Health::take_damage`], [`Health::heal`] or
+/// [`Health::set_temporary_hp`] did, for showing to the player.
+#[derive(Clone, Copy, Debug)]
+pub struct HpChange {
+    pub kind: HpChangeKind,
+    pub amount: u32,
+    pub hp_before: u8,
+    pub hp_after: u8,
+    pub temp_before: u8,
+    pub temp_after: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HpChangeKind {
+    Damage,
+    Healing,
+    TemporaryHp,
+}
+
+impl Health {
+    /// Temporary HP soak up damage first; whatever is left comes off the
+    /// current HP, which can't drop below zero.
+    ///
+    /// Dropping to 0 knocks the character unconscious and starts death
+    /// saves. Damage taken while already at 0 counts as a failed death save
+    /// and undoes any stabilisation.
+    pub fn take_damage(&mut self, amount: u32) -> HpChange {
+        self.change(HpChangeKind::Damage, amount, |health| {
+            let absorbed = amount.min(u32::from(health.temporary_hp)) as u8;
+            health.temporary_hp -= absorbed;
+            let remaining = amount - u32::from(absorbed);
+            if remaining == 0 {
+                return;
+            }
+
+            if health.current_hp == 0 {
+                if health.life_state() == LifeState::Stable {
+                    health.death_saves.successes = 0;
+                }
+                health.add_death_save_failures(1);
+            } else {
+                health.current_hp = u32::from(health.current_hp).saturating_sub(remaining) as u8;
+                if health.current_hp == 0 {
+                    health.unconscious = true;
+                    health.death_saves = DeathSaves::default();
+                }
+            }
+        })
+    }
+
+    /// Healing never raises current HP above the maximum and doesn't touch
+    /// temporary HP. Any healing at all ends death saves and wakes the
+    /// character up, unless they have already died.
+    pub fn heal(&mut self, amount: u32) -> HpChange {
+        self.change(HpChangeKind::Healing, amount, |health| {
+            if health.life_state() == LifeState::Dead {
+                return;
+            }
+            let healed = u32::from(health.current_hp) + amount;
+            health.current_hp = healed.min(u32::from(health.maximum_hp)) as u8;
+            if health.current_hp > 0 {
+                health.unconscious = false;
+                health.death_saves = DeathSaves::default();
+            }
+        })
+    }
+
+    /// Number of faces on the hit die, e.g. 8 for `"d8"`.
+    pub fn hit_die_sides(&self) -> Option<u32> {
+        let text = self.hit_dice_type.trim();
+        // Also accept the "1d8" spelling.
+        text.strip_prefix('1')
+            .unwrap_or(text)
+            .strip_prefix(['d', 'D'])?
+            .parse()
+            .ok()
+            .filter(|sides| *sides > 0)
+    }
+
+    pub fn life_state(&self) -> LifeState {
+        let saves = self.death_saves;
+        if saves.failures >= rules::DEATH_SAVES_NEEDED {
+            LifeState::Dead
+        } else if self.current_hp > 0 {
+            LifeState::Conscious
+        } else if saves.successes >= rules::DEATH_SAVES_NEEDED {
+            LifeState::Stable
+        } else {
+            LifeState::Dying
+        }
+    }
+
+    /// Records a death saving throw where the d20 came up `die`. Returns
+    /// `None` when the character isn't dying and so has nothing to roll.
+    pub fn roll_death_save(&mut self, die: u32) -> Option<DeathSaveOutcome> {
+        if self.life_state() != LifeState::Dying {
+            return None;
+        }
+
+        Some(match die {
+            20 => {
+                self.heal(1);
+                DeathSaveOutcome::Revived
+            }
+            1 => {
+                self.add_death_save_failures(2);
+                match self.life_state() {
+                    LifeState::Dead => DeathSaveOutcome::Died,
+                    _ => DeathSaveOutcome::DoubleFailure,
+                }
+            }
+            _ => self.record_death_save(die >= rules::DEATH_SAVE_DC)?,
+        })
+    }
+
+    /// Marks a death save made outside the app, e.g. with physical dice.
+    pub fn record_death_save(&mut self, success: bool) -> Option<DeathSaveOutcome> {
+        if self.life_state() != LifeState::Dying {
+            return None;
+        }
+
+        if !success {
+            self.add_death_save_failures(1);
+            return Some(match self.life_state() {
+                LifeState::Dead => DeathSaveOutcome::Died,
+                _ => DeathSaveOutcome::Failure,
+            });
+        }
+
+        self.death_saves.successes += 1;
+        Some(match self.life_state() {
+            LifeState::Stable => DeathSaveOutcome::Stabilized,
+            _ => DeathSaveOutcome::Success,
+        })
+    }
+
+    fn add_death_save_failures(&mut self, count: u8) {
+        self.unconscious = true;
+        self.death_saves.failures =
+            (self.death_saves.failures + count).min(rules::DEATH_SAVES_NEEDED);
+    }
+
+    /// Temporary HP don't stack: a new grant only replaces the current pool
+    /// when it is larger.
+    pub fn set_temporary_hp(&mut self, amount: u32) -> HpChange {
+        self.change(HpChangeKind::TemporaryHp, amount, |health| {
+            let granted = amount.min(u32::from(u8::MAX)) as u8;
+            health.temporary_hp = health.temporary_hp.max(granted);
+        })
+    }
+
+    fn change(
+        &mut self,
+        kind: HpChangeKind,
+        amount: u32,
+        apply: impl FnOnce(&mut Health),
+    ) -> HpChange {
+        let (hp_before, temp_before) = (self.current_hp, self.temporary_hp);
+        apply(self);
+        HpChange {
+            kind,
+            amount,
+            hp_before,
+            hp_after: self.current_hp,
+            temp_before,
+            temp_after: self.temporary_hp,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[allow(dead_code)]
+pub struct CharSheet {
+    pub schema_version: u32,
+    pub information: Information,
+    pub statistics: Statistics,
+    pub saving_throws: SavingThrows,
+    pub skills: Skills,
+    pub proficiencies_and_language: ProficienciesAndLanguage,
+    pub health: Health,
+    // Only casters have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spellcasting: Option<Spellcasting>,
+    /// Rage, ki points and other limited-use class features.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<Resource>,
+    #[serde(default, skip_serializing_if = "Inventory::is_empty")]
+    pub inventory: Inventory,
+    #[serde(default, skip_serializing_if = "Wallet::is_empty")]
+    pub wallet: Wallet,
+}
+
+impl CharSheet {
+    /// A level 1 character with every ability at 10, used when starting a
+    /// sheet from scratch.
+    pub fn blank() -> CharSheet {
+        CharSheet {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            information: Information {
+                level: 1,
+                ..Information::default()
+            },
+            statistics: Statistics {
+                strength: 10,
+                dexterity: 10,
+                constitution: 10,
+                intelligence: 10,
+                wisdom: 10,
+                charisma: 10,
+                ..Statistics::default()
+            },
+            health: Health {
+                armor_class: 10,
+                speed: 30,
+                hit_dice_type: "d8".into(),
+                total_hit_dice: 1,
+                current_hit_dice: 1,
+                ..Health::default()
+            },
+            ..CharSheet::default()
+        }
+    }
+
+    /// Checks the values serde can't, returning one message per problem.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if !(1..=20).contains(&self.information.level) {
+            problems.push(format!(
+                "level must be between 1 and 20, found {}",
+                self.information.level
+            ));
+        }
+        for stat in self.statistics.ability_scores() {
+            if !(rules::MIN_ABILITY_SCORE..=rules::MAX_ABILITY_SCORE).contains(&stat.value) {
+                problems.push(format!(
+                    "{} must be between {} and {}, found {}",
+                    stat.ability.abbreviation(),
+                    rules::MIN_ABILITY_SCORE,
+                    rules::MAX_ABILITY_SCORE,
+                    stat.value
+                ));
+            }
+        }
+        let max = rules::MAX_BONUS_OVERRIDE;
+        if let Some(bonus) = self.statistics.proficiency_bonus_override
+            && bonus > max as u8
+        {
+            problems.push(format!(
+                "the proficiency bonus override can't be above {max}, found {bonus}"
+            ));
+        }
+        let signed_overrides = std::iter::once(("initiative", self.health.initiative_override))
+            .chain(
+                self.skills
+                    .entries
+                    .iter()
+                    .map(|(skill, entry)| (skill.name(), entry.total_override)),
+            );
+        for (name, value) in signed_overrides {
+            if let Some(value) = value
+                && !(-max..=max).contains(&value)
+            {
+                problems.push(format!(
+                    "the {name} override must be between -{max} and {max}, found {value}"
+                ));
+            }
+        }
+        if self.health.current_hp > self.health.maximum_hp {
+            problems.push(format!(
+                "current HP ({}) is above maximum HP ({})",
+                self.health.current_hp, self.health.maximum_hp
+            ));
+        }
+        let saves = self.health.death_saves;
+        if saves.successes > rules::DEATH_SAVES_NEEDED || saves.failures > rules::DEATH_SAVES_NEEDED
+        {
+            problems.push(format!(
+                "death saves can't go past {}, found {} successes and {} failures",
+                rules::DEATH_SAVES_NEEDED,
+                saves.successes,
+                saves.failures
+            ));
+        }
+        if self.health.current_hit_dice > self.health.total_hit_dice {
+            problems.push(format!(
+                "{} hit dice remaining but only {} in total",
+                self.health.current_hit_dice, self.health.total_hit_dice
+            ));
+        }
+        if let Some(spellcasting) = &self.spellcasting {
+            spellcasting.validate(&mut problems);
+        }
+        for resource in &self.resources {
+            resource.validate(self, &mut problems);
+        }
+        self.inventory.validate(&mut problems);
+
+        problems
+    }
+
+    /// Brings every resource back within its maximum, which goes down with
+    /// the ability score or level a `max_formula` is worked out from.
+    pub fn clamp_resources(&mut self) {
+        let maxima: Vec<u8> = self.resources.iter().map(|r| r.max(self)).collect();
+        for (resource, max) in self.resources.iter_mut().zip(maxima) {
+            resource.current = resource.current.min(max);
+        }
+    }
+
+    pub fn proficiency_bonus(&self) -> Derived<u8> {
+        Derived::resolve(
+            rules::proficiency_bonus(self.information.level),
+            self.statistics.proficiency_bonus_override,
+        )
+    }
+
+    pub fn initiative(&self) -> Derived<i8> {
+        Derived::resolve(
+            rules::initiative(self.statistics.dexterity),
+            self.health.initiative_override,
+        )
+    }
+
+    pub fn passive_perception(&self) -> Derived<u8> {
+        let perception = self.skills.total(
+            Skill::Perception,
+            &self.statistics,
+            self.proficiency_bonus().value,
+        );
+
+        Derived::resolve(
+            rules::passive_score(perception.value),
+            self.statistics.passive_perception_override,
+        )
+    }
+
+    /// Pounds the character can carry.
+    pub fn carrying_capacity(&self) -> f64 {
+        rules::carrying_capacity(self.statistics.strength)
+    }
+
+    pub fn encumbrance(&self) -> Encumbrance {
+        self.inventory.encumbrance(self.statistics.strength)
+    }
+
+    /// The speed on the sheet, less whatever the load carried costs under
+    /// the variant encumbrance rules.
+    pub fn speed(&self) -> u8 {
+        self.health
+            .speed
+            .saturating_sub(self.inventory.speed_penalty(self.statistics.strength))
+    }
+
+    pub fn spell_save_dc(&self) -> Option<u8> {
+        let spellcasting = self.spellcasting.as_ref()?;
+        Some(rules::spell_save_dc(
+            self.proficiency_bonus().value,
+            self.statistics.modifier(spellcasting.ability),
+        ))
+    }
+
+    pub fn spell_attack_bonus(&self) -> Option<i8> {
+        let spellcasting = self.spellcasting.as_ref()?;
+        Some(rules::spell_attack_bonus(
+            self.proficiency_bonus().value,
+            self.statistics.modifier(spellcasting.ability),
+        ))
+    }
+
+    pub fn saving_throw_views(&self) -> [SavingThrowView; 6] {
+        self.saving_throws
+            .saving_throw_views(&self.statistics, self.proficiency_bonus().value)
+    }
+
+    pub fn skills_views(&self) -> [SkillsView; 18] {
+        self.skills
+            .skills_views(&self.statistics, self.proficiency_bonus().value)
+    }
+
+    /// The modifier added to a d20 rolled for `target`.
+    pub fn check_modifier(&self, target: RollTarget) -> i8 {
+        let prof_bonus = self.proficiency_bonus().value;
+
+        match target {
+            RollTarget::Ability(ability) => self.statistics.modifier(ability),
+            RollTarget::Save(ability) => {
+                let views = self
+                    .saving_throws
+                    .saving_throw_views(&self.statistics, prof_bonus);
+                views[ability as usize].value
+            }
+            RollTarget::Skill(skill) => {
+                self.skills.total(skill, &self.statistics, prof_bonus).value
+            }
+            RollTarget::Initiative => self.initiative().value,
+        }
+    }
+}
+
+/// Anything on the sheet that is rolled as a d20 plus a modifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollTarget {
+    Ability(Ability),
+    Save(Ability),
+    Skill(Skill),
+    Initiative,
+}
+
+impl RollTarget {
+    /// Accepts `initiative`, a skill (`athletics`), an ability check (`str`,
+    /// `strength`) or a saving throw (`str-save`, `strength_save`).
+    pub fn parse(name: &str) -> Option<RollTarget> {
+        if name.eq_ignore_ascii_case("initiative") {
+            return Some(RollTarget::Initiative);
+        }
+
+        let lower = name.to_ascii_lowercase();
+        if let Some(ability) = lower
+            .strip_suffix("-save")
+            .or_else(|| lower.strip_suffix("_save"))
+            .or_else(|| lower.strip_suffix(" save"))
+        {
+            return Ability::from_name(ability).map(RollTarget::Save);
+        }
+
+        Ability::from_name(name)
+            .map(RollTarget::Ability)
+            .or_else(|| Skill::from_name(name).map(RollTarget::Skill))
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            RollTarget::Ability(ability) => format!("{} check", ability.abbreviation()),
+            RollTarget::Save(ability) => format!("{} save", ability.abbreviation()),
+            RollTarget::Skill(skill) => skill.name().to_string(),
+            RollTarget::Initiative => "Initiative".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct StatView {
+    pub ability: Ability,
+    pub value: u8,
+    pub modifier: i8,
+}
+
+impl Statistics {
+    pub fn score(&self, ability: Ability) -> u8 {
+        match ability {
+            Ability::Strength => self.strength,
+            Ability::Dexterity => self.dexterity,
+            Ability::Constitution => self.constitution,
+            Ability::Intelligence => self.intelligence,
+            Ability::Wisdom => self.wisdom,
+            Ability::Charisma => self.charisma,
+        }
+    }
+
+    /// Sets a score, clamped to the range the rules allow.
+    pub fn set_score(&mut self, ability: Ability, value: u8) {
+        let value = value.clamp(rules::MIN_ABILITY_SCORE, rules::MAX_ABILITY_SCORE);
+        match ability {
+            Ability::Strength => self.strength = value,
+            Ability::Dexterity => self.dexterity = value,
+            Ability::Constitution => self.constitution = value,
+            Ability::Intelligence => self.intelligence = value,
+            Ability::Wisdom => self.wisdom = value,
+            Ability::Charisma => self.charisma = value,
+        }
+    }
+
+    pub fn modifier(&self, ability: Ability) -> i8 {
+        rules::ability_mod(self.score(ability))
+    }
+
+    pub fn ability_scores(&self) -> [StatView; 6] {
+        Ability::ALL.map(|ability| StatView {
+            ability,
+            value: self.score(ability),
+            modifier: self.modifier(ability),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SavingThrowView {
+    pub ability: Ability,
+    pub value: i8,
+    pub proficient: bool,
+}
+
+impl SavingThrows {
+    pub fn is_proficient(&self, ability: Ability) -> bool {
+        self.proficiencies.get(&ability).copied().unwrap_or(false)
+    }
+
+    /// Flips a saving throw between proficient and not.
+    pub fn toggle(&mut self, ability: Ability) {
+        let proficient = !self.is_proficient(ability);
+        self.proficiencies.insert(ability, proficient);
+    }
+
+    pub fn saving_throw_views(&self, stats: &Statistics, prof_bonus: u8) -> [SavingThrowView; 6] {
+        Ability::ALL.map(|ability| {
+            let proficient = self.is_proficient(ability);
+            let mut value = stats.modifier(ability);
+
+            if proficient {
+                value = value.saturating_add(rules::signed_bonus(prof_bonus));
+            }
+
+            SavingThrowView {
+                ability,
+                value,
+                proficient,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SkillsView {
+    pub skill: Skill,
+    pub value: i8,
+    pub sp: SkillProficiency,
+    pub overridden: bool,
+}
+
+impl Skills {
+    pub fn total(&self, skill: Skill, stats: &Statistics, prof_bonus: u8) -> Derived<i8> {
+        let entry = self.entries.get(&skill);
+        let sp = entry.map(|e| e.proficiency).unwrap_or_default();
+
+        Derived::resolve(
+            stats
+                .modifier(skill.ability())
+                .saturating_add(sp.bonus(prof_bonus)),
+            entry.and_then(|e| e.total_override),
+        )
+    }
+
+    /// Moves a skill on to the next proficiency level, wrapping from
+    /// expertise back to none. A hand-pinned total is left alone.
+    pub fn cycle(&mut self, skill: Skill) {
+        let entry = self.entries.entry(skill).or_default();
+        entry.proficiency = entry.proficiency.next();
+    }
+
+    pub fn skills_views(&self, stats: &Statistics, prof_bonus: u8) -> [SkillsView; 18] {
+        Skill::ALL.map(|skill| {
+            let total = self.total(skill, stats, prof_bonus);
+
+            SkillsView {
+                skill,
+                value: total.value,
+                sp: self
+                    .entries
+                    .get(&skill)
+                    .map(|e| e.proficiency)
+                    .unwrap_or_default(),
+                overridden: total.overridden,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillProficiency {
+    #[default]
+    None,
+    Proficient,
+    Expertise,
+}
+
+impl SkillProficiency {
+    /// Amount added to the ability modifier for this proficiency level.
+    pub fn bonus(self, prof_bonus: u8) -> i8 {
+        match self {
+            SkillProficiency::None => 0,
+            SkillProficiency::Proficient => rules::signed_bonus(prof_bonus),
+            SkillProficiency::Expertise => rules::signed_bonus(prof_bonus).saturating_mul(2),
+        }
+    }
+
+    /// None -> proficient -> expertise -> none.
+    pub fn next(self) -> SkillProficiency {
+        match self {
+            SkillProficiency::None => SkillProficiency::Proficient,
+            SkillProficiency::Proficient => SkillProficiency::Expertise,
+            SkillProficiency::Expertise => SkillProficiency::None,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            SkillProficiency::None => "○",
+            SkillProficiency::Proficient => "●",
+            SkillProficiency::Expertise => "◎",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(current_hp: u8) -> Health {
+        Health {
+            maximum_hp: 20,
+            current_hp,
+            ..Health::default()
+        }
+    }
+
+    fn health_at_zero_with_failures(failures: u8) -> Health {
+        let mut health = health(0);
+        health.unconscious = true;
+        health.death_saves.failures = failures;
+        health
+    }
+
+    #[test]
+    fn dropping_to_zero_knocks_out() {
+        let mut health = health(5);
+        health.temporary_hp = 3;
+        health.death_saves.failures = 2;
+
+        let change = health.take_damage(12);
+        assert_eq!((change.hp_after, change.temp_after), (0, 0));
+        assert!(health.unconscious);
+        // Saves left over from an earlier fall don't count.
+        assert_eq!(health.death_saves.failures, 0);
+        assert_eq!(health.life_state(), LifeState::Dying);
+    }
+
+    #[test]
+    fn damage_at_zero_is_a_failed_death_save() {
+        let mut health = health_at_zero_with_failures(0);
+        health.death_saves.successes = 3;
+        assert_eq!(health.life_state(), LifeState::Stable);
+
+        // Stabilised characters start dying again.
+        health.take_damage(1);
+        assert_eq!(health.life_state(), LifeState::Dying);
+        assert_eq!(
+            (health.death_saves.successes, health.death_saves.failures),
+            (0, 1)
+        );
+
+        // Temporary HP soak it up without a failure.
+        health.temporary_hp = 5;
+        health.take_damage(4);
+        assert_eq!(health.death_saves.failures, 1);
+
+        health.take_damage(10);
+        health.take_damage(10);
+        assert_eq!(health.death_saves.failures, rules::DEATH_SAVES_NEEDED);
+        assert_eq!(health.life_state(), LifeState::Dead);
+    }
+
+    #[test]
+    fn healing_wakes_the_dying_but_not_the_dead() {
+        let mut health = health_at_zero_with_failures(2);
+        health.heal(4);
+        assert_eq!(health.life_state(), LifeState::Conscious);
+        assert!(!health.unconscious);
+        assert_eq!(health.death_saves.failures, 0);
+
+        let mut health = health_at_zero_with_failures(3);
+        let change = health.heal(10);
+        assert_eq!(change.hp_after, 0);
+        assert!(health.unconscious);
+        assert_eq!(health.life_state(), LifeState::Dead);
+    }
+
+    #[test]
+    fn large_overrides_saturate_and_fail_validation() {
+        let mut sheet = CharSheet::blank();
+        sheet.statistics.proficiency_bonus_override = Some(200);
+        sheet.skills.entries.insert(
+            Skill::Perception,
+            SkillEntry {
+                proficiency: SkillProficiency::Expertise,
+                total_override: Some(i8::MAX),
+            },
+        );
+        sheet.skills.cycle(Skill::Athletics);
+        sheet.skills.cycle(Skill::Athletics);
+
+        assert_eq!(
+            sheet.check_modifier(RollTarget::Skill(Skill::Athletics)),
+            i8::MAX
+        );
+        assert_eq!(sheet.passive_perception().value, i8::MAX as u8);
+        sheet.saving_throws.toggle(Ability::Strength);
+        assert_eq!(
+            sheet.check_modifier(RollTarget::Save(Ability::Strength)),
+            i8::MAX
+        );
+
+        let problems = sheet.validate();
+        assert_eq!(problems.len(), 2, "{problems:?}");
+    }
+
+    #[test]
+    fn resources_come_down_with_a_lowered_maximum() {
+        let mut sheet = CharSheet::blank();
+        sheet.information.level = 4;
+        let resource = |current, max_formula: Option<&str>| Resource {
+            name: "Ki".into(),
+            max: 3,
+            current,
+            recharge: crate::resource::Recharge::ShortRest,
+            max_formula: max_formula.map(Into::into),
+        };
+        sheet.resources = vec![
+            resource(4, Some("@level")),
+            resource(3, Some("@level")),
+            resource(5, None),
+        ];
+
+        sheet.information.level = 2;
+        sheet.clamp_resources();
+        let current: Vec<u8> = sheet.resources.iter().map(|r| r.current).collect();
+        assert_eq!(current, [2, 2, 3]);
+
+        // Raising the maximum again doesn't give the uses back.
+        sheet.information.level = 4;
+        sheet.clamp_resources();
+        assert_eq!(sheet.resources[0].current, 2);
+    }
+}
@@ -0,0 +1,493 @@
+// Spellcasting: the ability a character casts with, their spell slots and
+// the spells they know. Casting a spell only ever spends a slot; what the
+// spell does is left to the table.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::rules;
+use crate::sheet::Ability;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Spellcasting {
+    pub ability: Ability,
+    #[serde(default)]
+    pub preparation: Preparation,
+    /// Slots by spell level, 1 to 9. Levels without slots are left out.
+    #[serde(default)]
+    pub slots: BTreeMap<u8, SpellSlots>,
+    // Warlocks only. Kept apart from `slots` because the two recharge on
+    // different rests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pact_magic: Option<PactMagic>,
+    #[serde(default)]
+    pub spells: Vec<Spell>,
+}
+
+/// Which of their spells a caster can cast.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preparation {
+    /// Clerics, druids, paladins and wizards only cast the spells they have
+    /// prepared.
+    #[default]
+    Prepared,
+    /// Bards, rangers, sorcerers and warlocks cast any spell they know.
+    Known,
+}
+
+impl Preparation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Preparation::Prepared => "prepared",
+            Preparation::Known => "known",
+        }
+    }
+}
+
+/// A warlock's pact slots: all of the same level, and all regained on a
+/// short rest.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PactMagic {
+    pub level: u8,
+    pub slots: SpellSlots,
+}
+
+/// Where the slot for a spell comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotSource {
+    /// A standard spell slot of this level.
+    Level(u8),
+    Pact,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct SpellSlots {
+    pub max: u8,
+    pub expended: u8,
+}
+
+impl SpellSlots {
+    pub fn available(self) -> u8 {
+        self.max.saturating_sub(self.expended)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Spell {
+    pub name: String,
+    /// 0 for a cantrip.
+    pub level: u8,
+    pub school: School,
+    #[serde(default)]
+    pub components: Components,
+    #[serde(default)]
+    pub concentration: bool,
+    #[serde(default)]
+    pub ritual: bool,
+    /// Only matters to casters who prepare their spells.
+    #[serde(default)]
+    pub prepared: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum School {
+    Abjuration,
+    Conjuration,
+    Divination,
+    Enchantment,
+    Evocation,
+    Illusion,
+    Necromancy,
+    Transmutation,
+}
+
+impl School {
+    pub fn name(self) -> &'static str {
+        match self {
+            School::Abjuration => "Abjuration",
+            School::Conjuration => "Conjuration",
+            School::Divination => "Divination",
+            School::Enchantment => "Enchantment",
+            School::Evocation => "Evocation",
+            School::Illusion => "Illusion",
+            School::Necromancy => "Necromancy",
+            School::Transmutation => "Transmutation",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Components {
+    pub verbal: bool,
+    pub somatic: bool,
+    /// What the material component is, if the spell has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+}
+
+impl Components {
+    /// `V, S, M` style shorthand, `-` when there are none.
+    pub fn short(&self) -> String {
+        let flags: Vec<&str> = [
+            (self.verbal, "V"),
+            (self.somatic, "S"),
+            (self.material.is_some(), "M"),
+        ]
+        .into_iter()
+        .filter_map(|(has, flag)| has.then_some(flag))
+        .collect();
+
+        if flags.is_empty() {
+            "-".into()
+        } else {
+            flags.join(", ")
+        }
+    }
+}
+
+/// `0` -> `Cantrip`, `3` -> `3rd`.
+pub fn level_name(level: u8) -> String {
+    let suffix = match level {
+        0 => return "Cantrip".into(),
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    };
+    format!("{level}{suffix}")
+}
+
+impl Spellcasting {
+    pub fn new(ability: Ability) -> Spellcasting {
+        Spellcasting {
+            ability,
+            preparation: Preparation::default(),
+            slots: BTreeMap::new(),
+            pact_magic: None,
+            spells: Vec::new(),
+        }
+    }
+
+    /// Whether `spell` can be cast: cantrips always can, and other spells
+    /// need preparing first unless the caster casts any spell they know.
+    pub fn castable(&self, spell: &Spell) -> bool {
+        spell.level == 0 || self.preparation == Preparation::Known || spell.prepared
+    }
+
+    /// The slots for spell `level`, none at all if the sheet lists none.
+    pub fn slots(&self, level: u8) -> SpellSlots {
+        self.slots.get(&level).copied().unwrap_or_default()
+    }
+
+    /// The level a slot from `source` casts at, `None` for a pact slot
+    /// when the character has no pact magic.
+    pub fn slot_level(&self, source: SlotSource) -> Option<u8> {
+        match source {
+            SlotSource::Level(level) => Some(level),
+            SlotSource::Pact => self.pact_magic.map(|pact| pact.level),
+        }
+    }
+
+    pub fn slots_of(&self, source: SlotSource) -> SpellSlots {
+        match source {
+            SlotSource::Level(level) => self.slots(level),
+            SlotSource::Pact => self.pact_magic.map(|pact| pact.slots).unwrap_or_default(),
+        }
+    }
+
+    fn slots_of_mut(&mut self, source: SlotSource) -> Option<&mut SpellSlots> {
+        match source {
+            SlotSource::Level(level) => self.slots.get_mut(&level),
+            SlotSource::Pact => self.pact_magic.as_mut().map(|pact| &mut pact.slots),
+        }
+    }
+
+    /// Casts spell `index` with a slot from `source`, which may be of a
+    /// higher level than the spell to upcast it. Cantrips don't use a slot,
+    /// whatever source is passed. Returns the level of the slot spent.
+    pub fn cast(&mut self, index: usize, source: SlotSource) -> Result<Option<u8>, String> {
+        let spell = self
+            .spells
+            .get(index)
+            .ok_or_else(|| "No spell selected".to_string())?;
+        if !self.castable(spell) {
+            return Err(format!("{} is not prepared", spell.name));
+        }
+        if spell.level == 0 {
+            return Ok(None);
+        }
+        let slot_level = self
+            .slot_level(source)
+            .ok_or_else(|| "This character has no pact magic".to_string())?;
+        if slot_level < spell.level {
+            return Err(format!(
+                "{} needs a slot of level {} or higher",
+                spell.name, spell.level
+            ));
+        }
+
+        match self.slots_of_mut(source) {
+            Some(slots) if slots.available() > 0 => slots.expended += 1,
+            _ if source == SlotSource::Pact => return Err("No pact slots left".into()),
+            _ => {
+                return Err(format!(
+                    "No {} level spell slots left",
+                    level_name(slot_level)
+                ));
+            }
+        }
+        Ok(Some(slot_level))
+    }
+
+    /// Gives back one expended slot from `source`. Returns whether there
+    /// was one to give back.
+    pub fn regain_slot(&mut self, source: SlotSource) -> bool {
+        match self.slots_of_mut(source) {
+            Some(slots) if slots.expended > 0 => {
+                slots.expended -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Sets how many slots of `level` the character has, keeping the
+    /// expended count within it.
+    pub fn set_max_slots(&mut self, level: u8, max: u8) {
+        if !(1..=rules::MAX_SPELL_LEVEL).contains(&level) {
+            return;
+        }
+        if max == 0 {
+            self.slots.remove(&level);
+            return;
+        }
+        let slots = self.slots.entry(level).or_default();
+        slots.max = max;
+        slots.expended = slots.expended.min(max);
+    }
+
+    /// Sets how many pact slots the character has. Taking the last one
+    /// away removes pact magic; adding one to a character without it starts
+    /// them at 1st level.
+    pub fn set_max_pact_slots(&mut self, max: u8) {
+        if max == 0 {
+            self.pact_magic = None;
+            return;
+        }
+        let pact = self.pact_magic.get_or_insert(PactMagic {
+            level: 1,
+            slots: SpellSlots::default(),
+        });
+        pact.slots.max = max;
+        pact.slots.expended = pact.slots.expended.min(max);
+    }
+
+    pub fn set_pact_level(&mut self, level: u8) {
+        if let Some(pact) = &mut self.pact_magic {
+            pact.level = level.clamp(1, rules::MAX_PACT_SLOT_LEVEL);
+        }
+    }
+
+    /// Expended standard slots, not counting pact slots.
+    pub fn expended_slots(&self) -> u16 {
+        self.slots
+            .values()
+            .map(|slots| u16::from(slots.expended))
+            .sum()
+    }
+
+    pub fn expended_pact_slots(&self) -> u8 {
+        self.pact_magic.map_or(0, |pact| pact.slots.expended)
+    }
+
+    /// Restores every expended standard slot, as a long rest does.
+    pub fn regain_all_slots(&mut self) {
+        for slots in self.slots.values_mut() {
+            slots.expended = 0;
+        }
+    }
+
+    /// Restores every pact slot, as any rest does.
+    pub fn regain_pact_slots(&mut self) {
+        if let Some(pact) = &mut self.pact_magic {
+            pact.slots.expended = 0;
+        }
+    }
+
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        for (level, slots) in &self.slots {
+            if !(1..=rules::MAX_SPELL_LEVEL).contains(level) {
+                problems.push(format!(
+                    "spell slots go from level 1 to {}, found level {level}",
+                    rules::MAX_SPELL_LEVEL
+                ));
+            }
+            if slots.expended > slots.max {
+                problems.push(format!(
+                    "{} expended level {level} spell slots but only {} in total",
+                    slots.expended, slots.max
+                ));
+            }
+        }
+        if let Some(pact) = self.pact_magic {
+            if !(1..=rules::MAX_PACT_SLOT_LEVEL).contains(&pact.level) {
+                problems.push(format!(
+                    "pact slots go from level 1 to {}, found level {}",
+                    rules::MAX_PACT_SLOT_LEVEL,
+                    pact.level
+                ));
+            }
+            if pact.slots.expended > pact.slots.max {
+                problems.push(format!(
+                    "{} expended pact slots but only {} in total",
+                    pact.slots.expended, pact.slots.max
+                ));
+            }
+        }
+        for spell in &self.spells {
+            if spell.level > rules::MAX_SPELL_LEVEL {
+                problems.push(format!(
+                    "{} is a level {} spell, spells only go up to level {}",
+                    spell.name,
+                    spell.level,
+                    rules::MAX_SPELL_LEVEL
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sheet::CharSheet;
+
+    fn spell(name: &str, level: u8, prepared: bool) -> Spell {
+        Spell {
+            name: name.into(),
+            level,
+            school: School::Evocation,
+            components: Components::default(),
+            concentration: false,
+            ritual: false,
+            prepared,
+        }
+    }
+
+    /// Two 1st level slots and one 3rd, a prepared Magic Missile, an
+    /// unprepared Shield and a cantrip.
+    fn wizard() -> Spellcasting {
+        let mut spellcasting = Spellcasting::new(Ability::Intelligence);
+        spellcasting.set_max_slots(1, 2);
+        spellcasting.set_max_slots(3, 1);
+        spellcasting.spells = vec![
+            spell("Magic Missile", 1, true),
+            spell("Shield", 1, false),
+            spell("Fire Bolt", 0, false),
+        ];
+        spellcasting
+    }
+
+    #[test]
+    fn a_spell_needs_a_school() {
+        let spell = r#"{ "name": "Mage Hand", "level": 0, "school": "conjuration" }"#;
+        let spell: Spell = serde_json::from_str(spell).unwrap();
+        assert_eq!(spell.school, School::Conjuration);
+
+        let err = serde_json::from_str::<Spell>(r#"{ "name": "Mage Hand", "level": 0 }"#)
+            .expect_err("a spell without a school");
+        assert!(
+            err.to_string().starts_with("missing field `school`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn casting_spends_a_slot_of_the_chosen_level() {
+        let mut spellcasting = wizard();
+        assert_eq!(spellcasting.cast(0, SlotSource::Level(1)), Ok(Some(1)));
+        assert_eq!(spellcasting.slots(1).available(), 1);
+
+        // Cantrips never use a slot.
+        assert_eq!(spellcasting.cast(2, SlotSource::Level(1)), Ok(None));
+        assert_eq!(spellcasting.slots(1).available(), 1);
+        assert_eq!(spellcasting.expended_slots(), 1);
+    }
+
+    #[test]
+    fn upcasting_spends_the_higher_slot() {
+        let mut spellcasting = wizard();
+        assert_eq!(spellcasting.cast(0, SlotSource::Level(3)), Ok(Some(3)));
+        assert_eq!(spellcasting.slots(3).available(), 0);
+        assert_eq!(spellcasting.slots(1).available(), 2);
+
+        spellcasting.spells.push(spell("Fireball", 3, true));
+        assert_eq!(
+            spellcasting.cast(3, SlotSource::Level(1)),
+            Err("Fireball needs a slot of level 3 or higher".into())
+        );
+    }
+
+    #[test]
+    fn casting_stops_when_the_slots_run_out() {
+        let mut spellcasting = wizard();
+        for _ in 0..2 {
+            spellcasting.cast(0, SlotSource::Level(1)).unwrap();
+        }
+        assert_eq!(
+            spellcasting.cast(0, SlotSource::Level(1)),
+            Err("No 1st level spell slots left".into())
+        );
+        assert_eq!(
+            spellcasting.cast(0, SlotSource::Level(2)),
+            Err("No 2nd level spell slots left".into())
+        );
+        assert_eq!(
+            spellcasting.cast(0, SlotSource::Pact),
+            Err("This character has no pact magic".into())
+        );
+        assert_eq!(spellcasting.expended_slots(), 2);
+    }
+
+    #[test]
+    fn only_prepared_casters_need_spells_prepared() {
+        let mut spellcasting = wizard();
+        assert_eq!(
+            spellcasting.cast(1, SlotSource::Level(1)),
+            Err("Shield is not prepared".into())
+        );
+
+        spellcasting.preparation = Preparation::Known;
+        assert_eq!(spellcasting.cast(1, SlotSource::Level(1)), Ok(Some(1)));
+    }
+
+    #[test]
+    fn expended_slots_add_up_past_a_byte() {
+        let mut spellcasting = Spellcasting::new(Ability::Charisma);
+        for level in 1..=rules::MAX_SPELL_LEVEL {
+            spellcasting.set_max_slots(level, u8::MAX);
+            spellcasting.slots.get_mut(&level).unwrap().expended = u8::MAX;
+        }
+        assert_eq!(spellcasting.expended_slots(), 9 * 255);
+    }
+
+    #[test]
+    fn save_dc_and_attack_bonus_follow_the_casting_ability() {
+        let mut sheet = CharSheet::blank();
+        assert_eq!(sheet.spell_save_dc(), None);
+
+        // Level 5 is +3 proficiency, and 18 Charisma is +4.
+        sheet.information.level = 5;
+        sheet.statistics.charisma = 18;
+        sheet.spellcasting = Some(Spellcasting::new(Ability::Charisma));
+        assert_eq!(sheet.spell_save_dc(), Some(15));
+        assert_eq!(sheet.spell_attack_bonus(), Some(7));
+
+        // A low casting ability can't take the DC below zero.
+        assert_eq!(rules::spell_save_dc(2, -5), 5);
+        assert_eq!(rules::spell_save_dc(0, i8::MIN), 0);
+        assert_eq!(rules::spell_attack_bonus(u8::MAX, i8::MAX), i8::MAX);
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::migrate::{self, MigrationError};
+use crate::sheet::CharSheet;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Migration(MigrationError),
+    Validation(Vec<String>),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "could not read the file: {err}"),
+            LoadError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "invalid JSON at line {line}, column {column}: {message}"),
+            LoadError::Migration(err) => write!(f, "{err}"),
+            LoadError::Validation(problems) => {
+                write!(f, "the sheet is not valid: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> LoadError {
+        LoadError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(err: serde_json::Error) -> LoadError {
+        if err.is_io() {
+            return LoadError::Io(err.into());
+        }
+
+        // Syntax errors carry a position in the file. Errors raised while
+        // mapping the (migrated) document onto `CharSheet` don't, so they
+        // are reported as a validation problem instead.
+        match (err.line(), err.column()) {
+            (0, _) => LoadError::Validation(vec![err.to_string()]),
+            (line, column) => {
+                // serde_json appends the position to its message; it is
+                // already carried separately.
+                let message = err.to_string();
+                let suffix = format!(" at line {line} column {column}");
+                LoadError::Parse {
+                    line,
+                    column,
+                    message: message
+                        .strip_suffix(&suffix)
+                        .unwrap_or(&message)
+                        .to_string(),
+                }
+            }
+        }
+    }
+}
+
+impl From<MigrationError> for LoadError {
+    fn from(err: MigrationError) -> LoadError {
+        LoadError::Migration(err)
+    }
+}
+
+/// Reads, migrates and validates the sheet stored at `path`.
+pub fn load_sheet(path: &str) -> Result<CharSheet, LoadError> {
+    let mut file = File::open(path)?;
+    let mut buff = String::new();
+    file.read_to_string(&mut buff)?;
+
+    let mut value: serde_json::Value = serde_json::from_str(&buff)?;
+    migrate::migrate(&mut value)?;
+    let mut char_sheet: CharSheet = serde_json::from_value(value)?;
+    char_sheet.clamp_resources();
+
+    let problems = char_sheet.validate();
+    if !problems.is_empty() {
+        return Err(LoadError::Validation(problems));
+    }
+
+    Ok(char_sheet)
+}
+
+/// How many previous versions of a sheet are kept when nothing else is
+/// configured.
+pub const DEFAULT_BACKUP_COUNT: usize = 5;
+
+/// A previous version of a sheet, kept next to it as
+/// `.<file name>.bak.<index>`. Index 1 is the most recent.
+pub struct Backup {
+    pub index: usize,
+    pub path: PathBuf,
+    /// When this version of the sheet was saved.
+    pub saved: Option<SystemTime>,
+}
+
+/// Writes `char_sheet` to `path` as pretty-printed JSON.
+///
+/// The current file is first rotated into the backups (keeping at most
+/// `backups` of them), then the new contents are written to a temporary
+/// file that is renamed over `path`, so a crash part way through never
+/// leaves a truncated sheet behind.
+pub fn save_sheet(path: &str, char_sheet: &CharSheet, backups: usize) -> io::Result<()> {
+    let contents = serde_json::to_string_pretty(char_sheet)?;
+    let path = Path::new(path);
+
+    if path.exists() {
+        rotate_backups(path, backups)?;
+    }
+
+    write_atomic(path, contents.as_bytes())
+}
+
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp_path = sibling(path, "", ".tmp");
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn rotate_backups(path: &Path, keep: usize) -> io::Result<()> {
+    // Whatever would be pushed past `keep` goes, including backups left over
+    // from when more of them were kept.
+    for backup in find_backups(path) {
+        if backup.index >= keep {
+            fs::remove_file(&backup.path)?;
+        }
+    }
+    if keep == 0 {
+        return Ok(());
+    }
+
+    for index in (1..keep).rev() {
+        let older = backup_path(path, index);
+        if older.exists() {
+            fs::rename(&older, backup_path(path, index + 1))?;
+        }
+    }
+
+    // Copy rather than rename so the sheet itself stays in place until the
+    // new version has been written. The copy is stamped with the time it was
+    // made, so it gets the sheet's own save time back; renames keep it.
+    let saved = fs::metadata(path)?.modified()?;
+    let newest = backup_path(path, 1);
+    fs::copy(path, &newest)?;
+    File::options()
+        .write(true)
+        .open(&newest)?
+        .set_modified(saved)
+}
+
+pub fn backup_path(path: &Path, index: usize) -> PathBuf {
+    sibling(path, ".", &format!(".bak.{index}"))
+}
+
+/// `dir/name` -> `dir/<prefix>name<suffix>`
+fn sibling(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    path.with_file_name(format!("{prefix}{file_name}{suffix}"))
+}
+
+/// Lists the backups kept for the sheet at `path`, newest first.
+pub fn list_backups(path: &str) -> Vec<Backup> {
+    find_backups(Path::new(path))
+}
+
+// Looks through the whole directory rather than counting up from 1, so a
+// backup that was deleted by hand doesn't hide the older ones.
+fn find_backups(path: &Path) -> Vec<Backup> {
+    let prefix = sibling(path, ".", ".bak.")
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let index = name.to_str()?.strip_prefix(&prefix)?;
+            if !index.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+            let index = index.parse().ok().filter(|index| *index > 0)?;
+            let metadata = entry
+                .metadata()
+                .ok()
+                .filter(|metadata| metadata.is_file())?;
+            Some(Backup {
+                index,
+                path: entry.path(),
+                saved: metadata.modified().ok(),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| backup.index);
+    backups
+}
+
+/// Formats `time` as `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own under the system temp dir, removed when the
+    /// test is done with it.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir =
+                std::env::temp_dir().join(format!("char-sheet-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn sheet(&self) -> String {
+            self.0.join("sheet.json").to_string_lossy().into_owned()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sheet_named(name: &str) -> CharSheet {
+        let mut sheet = CharSheet::blank();
+        sheet.information.character_name = name.into();
+        sheet
+    }
+
+    fn saved_name(path: &Path) -> String {
+        load_sheet(&path.to_string_lossy())
+            .unwrap()
+            .information
+            .character_name
+    }
+
+    #[test]
+    fn a_failed_write_leaves_the_sheet_alone() {
+        let dir = TempDir::new("failed-write");
+        let path = dir.sheet();
+        save_sheet(&path, &sheet_named("Original"), 0).unwrap();
+
+        // The temporary file can't be created where a directory is in the way.
+        fs::create_dir(sibling(Path::new(&path), "", ".tmp")).unwrap();
+        assert!(save_sheet(&path, &sheet_named("Replacement"), 0).is_err());
+
+        assert_eq!(saved_name(Path::new(&path)), "Original");
+    }
+
+    #[test]
+    fn keeps_the_newest_backups() {
+        let dir = TempDir::new("rotation");
+        let path = dir.sheet();
+        for version in 1..=5 {
+            save_sheet(&path, &sheet_named(&format!("v{version}")), 3).unwrap();
+        }
+
+        let backups = list_backups(&path);
+        assert_eq!(
+            backups.iter().map(|b| b.index).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        let names: Vec<_> = backups.iter().map(|b| saved_name(&b.path)).collect();
+        assert_eq!(names, ["v4", "v3", "v2"]);
+        assert_eq!(saved_name(Path::new(&path)), "v5");
+
+        // Keeping fewer drops the ones past the new limit.
+        save_sheet(&path, &sheet_named("v6"), 1).unwrap();
+        let backups = list_backups(&path);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(saved_name(&backups[0].path), "v5");
+    }
+
+    #[test]
+    fn backups_keep_the_time_their_version_was_saved() {
+        let dir = TempDir::new("saved-time");
+        let path = dir.sheet();
+        save_sheet(&path, &sheet_named("v1"), 2).unwrap();
+        let saved = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(saved)
+            .unwrap();
+
+        save_sheet(&path, &sheet_named("v2"), 2).unwrap();
+        save_sheet(&path, &sheet_named("v3"), 2).unwrap();
+        let backups = list_backups(&path);
+        assert_eq!(saved_name(&backups[1].path), "v1");
+        assert_eq!(backups[1].saved, Some(saved));
+        assert_ne!(backups[0].saved, Some(saved));
+    }
+
+    #[test]
+    fn lists_backups_past_a_gap() {
+        let dir = TempDir::new("gap");
+        let path = dir.sheet();
+        for version in 1..=4 {
+            save_sheet(&path, &sheet_named(&format!("v{version}")), 5).unwrap();
+        }
+        fs::remove_file(backup_path(Path::new(&path), 2)).unwrap();
+
+        let backups = list_backups(&path);
+        assert_eq!(backups.iter().map(|b| b.index).collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(saved_name(&backups[1].path), "v1");
+    }
+
+    #[test]
+    fn loading_brings_resources_within_their_maximum() {
+        let dir = TempDir::new("resources");
+        let path = dir.sheet();
+        let mut sheet = CharSheet::blank();
+        sheet.resources.push(crate::resource::Resource {
+            name: "Ki".into(),
+            max: 0,
+            current: 4,
+            recharge: crate::resource::Recharge::ShortRest,
+            max_formula: Some("@level".into()),
+        });
+        save_sheet(&path, &sheet, 0).unwrap();
+
+        assert_eq!(load_sheet(&path).unwrap().resources[0].current, 1);
+    }
+}
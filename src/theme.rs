@@ -0,0 +1,76 @@
+use ratatui::style::Color;
+
+/// The colours the UI draws with, picked with `--theme`.
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub primary: Color,    // panel borders, title and the mode indicator
+    pub text: Color,       // ability boxes and typed input
+    pub positive: Color,   // modifiers of zero or more
+    pub negative: Color,   // negative modifiers and key hints
+    pub error: Color,      // load errors and the exit prompt
+    pub health: Color,     // the health panel and its buttons
+    pub notice: Color,     // status messages and the unsaved marker
+    pub overridden: Color, // values pinned by hand
+    pub secondary: Color,  // secondary screens such as backups
+    pub popup_bg: Color,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 3] = ["dark", "light", "mono"];
+
+    pub const DARK: Theme = Theme {
+        primary: Color::Green,
+        text: Color::White,
+        positive: Color::Green,
+        negative: Color::Red,
+        error: Color::LightRed,
+        health: Color::Yellow,
+        notice: Color::Yellow,
+        overridden: Color::Magenta,
+        secondary: Color::Cyan,
+        popup_bg: Color::DarkGray,
+    };
+
+    pub const LIGHT: Theme = Theme {
+        primary: Color::Blue,
+        text: Color::Black,
+        positive: Color::Green,
+        negative: Color::Red,
+        error: Color::Red,
+        health: Color::Magenta,
+        notice: Color::Magenta,
+        overridden: Color::Magenta,
+        secondary: Color::Blue,
+        popup_bg: Color::Gray,
+    };
+
+    // Leaves every colour to the terminal, for monochrome displays or
+    // colour-blind friendly setups.
+    pub const MONO: Theme = Theme {
+        primary: Color::Reset,
+        text: Color::Reset,
+        positive: Color::Reset,
+        negative: Color::Reset,
+        error: Color::Reset,
+        health: Color::Reset,
+        notice: Color::Reset,
+        overridden: Color::Reset,
+        secondary: Color::Reset,
+        popup_bg: Color::Reset,
+    };
+
+    pub fn by_name(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::DARK),
+            "light" => Some(Theme::LIGHT),
+            "mono" => Some(Theme::MONO),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::DARK
+    }
+}
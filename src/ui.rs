@@ -1,60 +1,170 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
+use char_sheet::dice::Advantage;
+use char_sheet::history::RollStats;
+use char_sheet::inventory::{self, ContentsWeight, Encumbrance};
+use char_sheet::rest::RestKind;
+use char_sheet::rules;
+use char_sheet::sheet::{
+    Ability, CharSheet, HpChange, HpChangeKind, LifeState, RollTarget, SavingThrowView, SkillsView,
+    StatView,
+};
+use char_sheet::spells::{self, SlotSource, Spellcasting};
+use char_sheet::storage;
+use char_sheet::wallet::{self, Coin};
+
 use crate::app::{
-    App, CurrentScreen, HealthView, Hover, SavingThrowView, SkillsView, StatView, ViewState,
+    App, CurrEditInformation, CurrentScreen, HealthView, Hover, InfoView, MoneyKind, PathAction,
+    ProficiencyTarget, RollEntry, StatsView, ViewState,
 };
+use crate::input::TextInput;
+use crate::theme::Theme;
 
 use std::rc::Rc;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Draws one ability box. While its score is being typed the input replaces
+/// the score.
+fn render_stat(
+    frame: &mut Frame,
+    stat: StatView,
+    area: ratatui::layout::Rect,
+    theme: &Theme,
+    selected: bool,
+    input: Option<&TextInput>,
+) {
+    let style = Style::default().fg(theme.text);
+    let line = match input {
+        Some(input) => {
+            let mut spans = vec![Span::raw(format!("{:+} (", stat.modifier))];
+            spans.extend(input_spans(input, style));
+            spans.push(Span::raw(")"));
+            Line::from(spans)
+        }
+        None => Line::from(format!("{:+} ({:})", stat.modifier, stat.value)),
+    };
 
-fn render_stat(frame: &mut Frame, stat: StatView, area: ratatui::layout::Rect) {
-    let lines = vec![Line::from(format!("{:+} ({:})", stat.modifier, stat.value))];
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(stat.ability.abbreviation());
+    if selected {
+        block = block.border_style(
+            Style::default()
+                .fg(theme.notice)
+                .add_modifier(Modifier::BOLD),
+        );
+    }
 
-    let paragraph = Paragraph::new(lines)
-        .block(Block::default().borders(Borders::ALL).title(stat.name))
+    let paragraph = Paragraph::new(line)
+        .block(block)
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::White));
+        .style(style);
 
     frame.render_widget(paragraph, area);
 }
 
-fn render_saving_throw(frame: &mut Frame, st: SavingThrowView, area: Rect) {
+fn render_saving_throw(
+    frame: &mut Frame,
+    st: SavingThrowView,
+    area: Rect,
+    theme: &Theme,
+    selected: bool,
+) {
     let symbol = if st.proficient { "●" } else { "○" };
 
     let value_style = if st.value >= 0 {
-        Style::default().fg(Color::Green)
+        Style::default().fg(theme.positive)
     } else {
-        Style::default().fg(Color::Red)
+        Style::default().fg(theme.negative)
     };
 
     let line = Line::from(vec![
         Span::raw(format!("{} ", symbol)),
-        Span::raw(format!("{:<3} ", st.name)),
+        Span::raw(format!("{:<3} ", st.ability.abbreviation())),
         Span::styled(format!("{:+}", st.value), value_style),
     ]);
+    let line = if selected {
+        line.style(Style::default().add_modifier(Modifier::REVERSED))
+    } else {
+        line
+    };
 
     frame.render_widget(Paragraph::new(line), area);
 }
 
-fn skill_to_list_item(skill: &SkillsView) -> ListItem<'static> {
-    ListItem::new(format!(
-        "{} {:<14} {:+}",
-        skill.sp.symbol(),
-        skill.name,
-        skill.value,
-    ))
+fn skill_to_list_item(skill: &SkillsView, theme: &Theme, selected: bool) -> ListItem<'static> {
+    let name = format!(
+        "{} ({})",
+        skill.skill.name(),
+        skill.skill.ability().short_name()
+    );
+
+    let item = ListItem::new(Line::from(vec![
+        Span::raw(format!("{} {:<21} ", skill.sp.symbol(), name)),
+        derived_span(format!("{:+}", skill.value), skill.overridden, theme),
+    ]));
+    if selected {
+        item.style(Style::default().add_modifier(Modifier::REVERSED))
+    } else {
+        item
+    }
 }
 
-fn draw_char_info(frame: &mut Frame, area: Rect, app: &App) {
+/// Values that were pinned by hand instead of computed by the rules get an
+/// asterisk so it's obvious they won't follow level or ability changes.
+fn derived_span(text: String, overridden: bool, theme: &Theme) -> Span<'static> {
+    if overridden {
+        Span::styled(
+            format!("{text}*"),
+            Style::default()
+                .fg(theme.overridden)
+                .add_modifier(Modifier::ITALIC),
+        )
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// Renders `input` with the character under the cursor highlighted.
+fn input_spans(input: &TextInput, style: Style) -> Vec<Span<'static>> {
+    let chars: Vec<char> = input.value().chars().collect();
+    let cursor = input.cursor();
+    let before: String = chars[..cursor].iter().collect();
+    let under = chars.get(cursor).map_or(" ".to_string(), char::to_string);
+    let after: String = chars.iter().skip(cursor + 1).collect();
+
+    vec![
+        Span::styled(before, style),
+        Span::styled(under, style.add_modifier(Modifier::REVERSED)),
+        Span::styled(after, style),
+    ]
+}
+
+/// The speed after encumbrance, picked out when the load slows the
+/// character down.
+fn speed_span(sheet: &CharSheet, theme: &Theme) -> Span<'static> {
+    let speed = sheet.speed();
+    if speed < sheet.health.speed {
+        Span::styled(
+            format!("{speed} ({})", sheet.encumbrance().label().to_lowercase()),
+            Style::default().fg(theme.notice),
+        )
+    } else {
+        Span::raw(speed.to_string())
+    }
+}
+
+fn draw_char_info(frame: &mut Frame, area: Rect, app: &App, view: &mut InfoView) {
     let info_blk = Block::default()
         .borders(Borders::ALL)
         .title("Character Information")
-        .style(Style::default().fg(Color::Green));
+        .style(Style::default().fg(app.theme.primary));
     frame.render_widget(info_blk.clone(), area);
 
     let inner_info_frame = info_blk.inner(area);
@@ -68,25 +178,48 @@ fn draw_char_info(frame: &mut Frame, area: Rect, app: &App) {
             Constraint::Length(char_info_width - 10), // slot 2
         ])
         .split(inner_info_frame);
-    let info_list = app.char_sheet.information.information_to_list_item();
     let char_info_row_len = 3;
 
-    let char_info_items_zero: Vec<ListItem> = info_list[0..char_info_row_len].to_vec();
-    let char_info_items_one: Vec<ListItem> =
-        info_list[char_info_row_len..char_info_row_len * 2].to_vec();
-    let end = 8;
-    let char_info_items_two: Vec<ListItem> = info_list[char_info_row_len * 2..end].to_vec();
+    let editing = matches!(app.current_screen, CurrentScreen::Editing);
+    view.field_rects.clear();
 
-    frame.render_widget(List::new(char_info_items_zero), char_info_rows[0]);
-    frame.render_widget(List::new(char_info_items_one), char_info_rows[1]);
-    frame.render_widget(List::new(char_info_items_two), char_info_rows[2]);
+    for (i, field) in CurrEditInformation::ALL.into_iter().enumerate() {
+        let column = char_info_rows[i / char_info_row_len];
+        let row = (i % char_info_row_len) as u16;
+        if row >= column.height {
+            continue;
+        }
+        let rect = Rect::new(column.x, column.y + row, column.width, 1);
+        view.field_rects.push((field, rect));
+
+        let selected = editing && field == app.edit_field;
+        let label = Span::raw(format!("{}: ", field.label()));
+        let line = match &app.edit_input {
+            Some(input) if selected => {
+                let mut spans = vec![label];
+                spans.extend(input_spans(input, Style::default().fg(app.theme.text)));
+                Line::from(spans)
+            }
+            _ => {
+                let value = field.value(&app.char_sheet.information);
+                let line = Line::from(vec![label, Span::raw(value)]);
+                if selected {
+                    line.style(Style::default().add_modifier(Modifier::REVERSED))
+                } else {
+                    line
+                }
+            }
+        };
+
+        frame.render_widget(Paragraph::new(line), rect);
+    }
 }
 
-fn draw_abilities(frame: &mut Frame, area: Rect, app: &App) {
+fn draw_abilities(frame: &mut Frame, area: Rect, app: &App, view: &mut StatsView) {
     let stats_blk = Block::default()
         .borders(Borders::ALL)
         .title("Abilities")
-        .style(Style::default().fg(Color::Green));
+        .style(Style::default().fg(app.theme.primary));
     frame.render_widget(stats_blk.clone(), area);
 
     let stats_sv = app.char_sheet.statistics.ability_scores();
@@ -145,24 +278,30 @@ fn draw_abilities(frame: &mut Frame, area: Rect, app: &App) {
         .split(stats_row3);
 
     // Render the stats 2 per row and modifiers here:
-    for (stat, chunk) in stats_sv.into_iter().take(2).zip(row1_chunks.iter()) {
-        render_stat(frame, stat, *chunk);
-    }
-
-    // Render the stats 2 per row and modifiers here:
-    for (stat, chunk) in stats_sv.into_iter().skip(2).zip(row2_chunks.iter()) {
-        render_stat(frame, stat, *chunk);
-    }
-
-    // Render the stats 2 per row and modifiers here:
-    for (stat, chunk) in stats_sv.into_iter().skip(4).zip(row3_chunks.iter()) {
-        render_stat(frame, stat, *chunk);
+    let editing = matches!(app.current_screen, CurrentScreen::EditingAbilities);
+    let rolling = matches!(app.current_screen, CurrentScreen::Rolling);
+    // Initiative has no box of its own, so it shows up on Dexterity.
+    let roll_selected = |target: RollTarget| {
+        rolling
+            && (app.roll_target == target
+                || target == RollTarget::Ability(Ability::Dexterity)
+                    && app.roll_target == RollTarget::Initiative)
+    };
+    let stat_chunks = row1_chunks
+        .iter()
+        .chain(row2_chunks.iter())
+        .chain(row3_chunks.iter());
+    view.ability_rects.clear();
+    view.proficiency_rects.clear();
+    for (stat, chunk) in stats_sv.into_iter().zip(stat_chunks) {
+        view.ability_rects.push((stat.ability, *chunk));
+        let selected = editing && stat.ability == app.edit_ability
+            || roll_selected(RollTarget::Ability(stat.ability));
+        let input = app.edit_input.as_ref().filter(|_| selected);
+        render_stat(frame, stat, *chunk, &app.theme, selected, input);
     }
 
-    let saving_throws = app
-        .char_sheet
-        .saving_throws
-        .saving_throw_views(&app.char_sheet.statistics);
+    let saving_throws = app.char_sheet.saving_throw_views();
 
     let sav_thr_blk = Block::default()
         .borders(Borders::ALL)
@@ -177,8 +316,13 @@ fn draw_abilities(frame: &mut Frame, area: Rect, app: &App) {
 
     frame.render_widget(sav_thr_blk, ability_chunks[1]);
 
+    let choosing = matches!(app.current_screen, CurrentScreen::EditingProficiencies);
     for (st, row) in saving_throws.into_iter().zip(svn_thr_rows.iter()) {
-        render_saving_throw(frame, st, *row);
+        let target = ProficiencyTarget::Save(st.ability);
+        view.proficiency_rects.push((target, *row));
+        let selected =
+            choosing && app.edit_proficiency == target || roll_selected(target.roll_target());
+        render_saving_throw(frame, st, *row, &app.theme, selected);
     }
 
     let skills_blk = Block::default().borders(Borders::ALL).title("Skills");
@@ -188,7 +332,7 @@ fn draw_abilities(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(&skills_blk, ability_chunks[2]);
 
     // Get the skills_view array
-    let skills_views = app.char_sheet.skills.skills_views();
+    let skills_views = app.char_sheet.skills_views();
     let skills_row_size = skills_views.len() / 2;
     let skills_box_width = 28;
     let skills_rows = Layout::default()
@@ -199,15 +343,31 @@ fn draw_abilities(frame: &mut Frame, area: Rect, app: &App) {
         ])
         .split(skills_inner);
 
+    for (i, skill) in skills_views.iter().enumerate() {
+        let column = skills_rows[i / skills_row_size];
+        let row = (i % skills_row_size) as u16;
+        if row < column.height {
+            let rect = Rect::new(column.x, column.y + row, column.width, 1);
+            view.proficiency_rects
+                .push((ProficiencyTarget::Skill(skill.skill), rect));
+        }
+    }
+
+    let skill_item = |skill: &SkillsView| {
+        let target = ProficiencyTarget::Skill(skill.skill);
+        let selected =
+            choosing && app.edit_proficiency == target || roll_selected(target.roll_target());
+        skill_to_list_item(skill, &app.theme, selected)
+    };
     let skills_items_zero: Vec<ListItem> = skills_views
         .iter()
         .take(skills_row_size)
-        .map(skill_to_list_item)
+        .map(skill_item)
         .collect();
     let skills_items_one: Vec<ListItem> = skills_views
         .iter()
         .skip(skills_row_size)
-        .map(skill_to_list_item)
+        .map(skill_item)
         .collect();
 
     frame.render_widget(List::new(skills_items_zero), skills_rows[0]);
@@ -218,7 +378,7 @@ fn draw_health(frame: &mut Frame, area: Rect, app: &App, view: &mut HealthView)
     let health_blk = Block::default()
         .borders(Borders::ALL)
         .title("Health")
-        .style(Style::default().fg(Color::Yellow));
+        .style(Style::default().fg(app.theme.health));
 
     frame.render_widget(health_blk.clone(), area);
 
@@ -267,6 +427,43 @@ fn draw_health(frame: &mut Frame, area: Rect, app: &App, view: &mut HealthView)
 
     frame.render_widget(temp_hp, hp_row[1]);
 
+    let sheet = &app.char_sheet;
+    let initiative = sheet.initiative();
+    let prof_bonus = sheet.proficiency_bonus();
+    let passive_perception = sheet.passive_perception();
+
+    let combat_stats = Paragraph::new(Line::from(vec![
+        Span::raw(format!("AC: {}  ", sheet.health.armor_class)),
+        Span::raw("Initiative: "),
+        derived_span(
+            format!("{:+}", initiative.value),
+            initiative.overridden,
+            &app.theme,
+        ),
+        Span::raw("  Speed: "),
+        speed_span(sheet, &app.theme),
+        Span::raw("  Proficiency: "),
+        derived_span(
+            format!("{:+}", prof_bonus.value),
+            prof_bonus.overridden,
+            &app.theme,
+        ),
+        Span::raw("  Passive Perception: "),
+        derived_span(
+            passive_perception.value.to_string(),
+            passive_perception.overridden,
+            &app.theme,
+        ),
+    ]));
+
+    view.roll_rect = Rect::default();
+    view.success_rect = Rect::default();
+    view.failure_rect = Rect::default();
+    match sheet.health.life_state() {
+        LifeState::Conscious => frame.render_widget(combat_stats, health_rows[1]),
+        state => draw_death_saves(frame, health_rows[1], app, state, view),
+    }
+
     let health_controls = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
@@ -281,7 +478,7 @@ fn draw_health(frame: &mut Frame, area: Rect, app: &App, view: &mut HealthView)
 
     let minus_style = if matches!(view.hover, Hover::Minus) {
         Style::default()
-            .fg(Color::Yellow)
+            .fg(app.theme.health)
             .add_modifier(Modifier::REVERSED)
     } else {
         Style::default().add_modifier(Modifier::REVERSED)
@@ -289,7 +486,7 @@ fn draw_health(frame: &mut Frame, area: Rect, app: &App, view: &mut HealthView)
 
     let plus_style = if matches!(view.hover, Hover::Plus) {
         Style::default()
-            .fg(Color::Yellow)
+            .fg(app.theme.health)
             .add_modifier(Modifier::REVERSED)
     } else {
         Style::default().add_modifier(Modifier::REVERSED)
@@ -302,9 +499,27 @@ fn draw_health(frame: &mut Frame, area: Rect, app: &App, view: &mut HealthView)
         health_controls[0],
     );
 
-    // Render the label
+    // Render the label, or the amount being typed
+    let label = match (&app.hp_prompt, &app.last_hp_change) {
+        (Some(prompt), _) => {
+            let mut spans = vec![Span::raw(match prompt.kind {
+                HpChangeKind::Damage => "Damage (amount or dice): ",
+                HpChangeKind::Healing => "Heal (amount or dice): ",
+                HpChangeKind::TemporaryHp => "Temporary HP (amount or dice): ",
+            })];
+            spans.extend(input_spans(
+                &prompt.input,
+                Style::default().fg(app.theme.text),
+            ));
+            Line::from(spans)
+        }
+        (None, Some(change)) => Line::from(describe_hp_change(change)),
+        (None, None) => {
+            Line::from("(d) damage / (h) heal / (t) temp HP / (s) short rest / (l) long rest")
+        }
+    };
     frame.render_widget(
-        Paragraph::new("Adjust HP").alignment(Alignment::Center),
+        Paragraph::new(label).alignment(Alignment::Center),
         health_controls[1],
     );
 
@@ -316,7 +531,242 @@ fn draw_health(frame: &mut Frame, area: Rect, app: &App, view: &mut HealthView)
     );
 }
 
-pub fn draw_title(frame: &mut Frame) -> Rc<[Rect]> {
+/// Replaces the combat stats while the character is at 0 HP.
+fn draw_death_saves(
+    frame: &mut Frame,
+    area: Rect,
+    app: &App,
+    state: LifeState,
+    view: &mut HealthView,
+) {
+    let saves = app.char_sheet.health.death_saves;
+    let pips = |count: u8| {
+        (0..rules::DEATH_SAVES_NEEDED)
+            .map(|i| if i < count { "●" } else { "○" })
+            .collect::<String>()
+    };
+    let (label, label_style) = match state {
+        LifeState::Dead => ("Dead", Style::default().fg(app.theme.error)),
+        LifeState::Stable => ("Stable", Style::default().fg(app.theme.positive)),
+        _ => ("Dying", Style::default().fg(app.theme.negative)),
+    };
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(10), // [ Roll ]
+            Constraint::Length(1),
+            Constraint::Length(13), // [ Success ]
+            Constraint::Length(1),
+            Constraint::Length(13), // [ Failure ]
+        ])
+        .split(area);
+
+    let mut spans = vec![
+        Span::styled(label, label_style.add_modifier(Modifier::BOLD)),
+        Span::raw("  Death saves  Successes: "),
+        Span::styled(
+            pips(saves.successes),
+            Style::default().fg(app.theme.positive),
+        ),
+        Span::raw("  Failures: "),
+        Span::styled(
+            pips(saves.failures),
+            Style::default().fg(app.theme.negative),
+        ),
+    ];
+    if state == LifeState::Dying {
+        spans.push(Span::raw("  (r) to roll"));
+    }
+    let line = Line::from(spans);
+    frame.render_widget(Paragraph::new(line), columns[0]);
+
+    if state != LifeState::Dying {
+        return;
+    }
+    view.roll_rect = columns[1];
+    view.success_rect = columns[3];
+    view.failure_rect = columns[5];
+    let button = Style::default().add_modifier(Modifier::REVERSED);
+    for (text, rect) in [
+        ("[ Roll ]", columns[1]),
+        ("[ Success ]", columns[3]),
+        ("[ Failure ]", columns[5]),
+    ] {
+        frame.render_widget(
+            Paragraph::new(text)
+                .alignment(Alignment::Center)
+                .style(button),
+            rect,
+        );
+    }
+}
+
+/// The roll log, newest roll first. The newest one is highlighted so a fresh
+/// roll is easy to spot, and natural 20s and 1s on a d20 stand out.
+fn draw_roll_log(frame: &mut Frame, area: Rect, app: &App, view_state: &mut ViewState) {
+    let mut title = if app.roll_log_scroll > 0 {
+        format!("Rolls ({} newer above)", app.roll_log_scroll)
+    } else {
+        "Rolls".to_string()
+    };
+    match app.next_advantage {
+        Some(Advantage::Advantage) => title.push_str(" - next roll with advantage"),
+        Some(Advantage::Disadvantage) => title.push_str(" - next roll with disadvantage"),
+        None => {}
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().fg(app.theme.primary));
+    view_state.roll_log = area;
+
+    let newest = app.roll_log.len().checked_sub(1);
+    let lines: Vec<Line> = if app.roll_log.is_empty() {
+        [
+            "No rolls yet. Click an ability, save or skill to roll it, or press (r).",
+            "Shift-click or (v) for advantage, Ctrl-click or (v) twice for disadvantage.",
+            "Right-click a save or skill to change its proficiency.",
+        ]
+        .into_iter()
+        .map(|help| Line::styled(help, Style::default().fg(app.theme.text)))
+        .collect()
+    } else {
+        app.roll_log
+            .iter()
+            .enumerate()
+            .rev()
+            .skip(app.roll_log_scroll)
+            .map(|(i, entry)| roll_line(entry, Some(i) == newest, app))
+            .collect()
+    };
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+// Past this many uses a resource shows only its count, so ki or sorcery
+// points at high level don't run off the panel.
+const MAX_RESOURCE_PIPS: u8 = 20;
+
+fn draw_resources(frame: &mut Frame, area: Rect, app: &App, view_state: &mut ViewState) {
+    let choosing = matches!(app.current_screen, CurrentScreen::Resources);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Resources")
+        .style(Style::default().fg(app.theme.primary));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let sheet = &app.char_sheet;
+    view_state.resources.clear();
+    for (i, resource) in sheet.resources.iter().enumerate() {
+        let y = inner.y + i as u16;
+        if y >= inner.bottom() {
+            break;
+        }
+        let row = Rect::new(inner.x, y, inner.width, 1);
+        view_state.resources.push(row);
+
+        let max = resource.max(sheet);
+        let current = resource.current.min(max);
+        let name_style = if choosing && i == app.selected_resource {
+            Style::default()
+                .fg(app.theme.text)
+                .add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(app.theme.text)
+        };
+        let mut spans = vec![Span::styled(resource.name.clone(), name_style)];
+        if max <= MAX_RESOURCE_PIPS {
+            spans.push(Span::styled(
+                format!(
+                    " {}{}",
+                    "●".repeat(current.into()),
+                    "○".repeat((max - current).into())
+                ),
+                Style::default().fg(app.theme.positive),
+            ));
+        }
+        spans.push(Span::styled(
+            format!(" {current}/{max}"),
+            Style::default()
+                .fg(app.theme.text)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(
+            format!(" ({})", resource.recharge.label()),
+            Style::default().fg(app.theme.secondary),
+        ));
+        frame.render_widget(Paragraph::new(Line::from(spans)), row);
+    }
+}
+
+fn roll_line(entry: &RollEntry, latest: bool, app: &App) -> Line<'static> {
+    let text_style = if latest {
+        Style::default()
+            .fg(app.theme.text)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(app.theme.text)
+    };
+
+    let mut spans = vec![
+        Span::styled(format!("{}: ", entry.label), text_style),
+        Span::styled(
+            format!("{} ", entry.expression),
+            Style::default().fg(app.theme.secondary),
+        ),
+        Span::styled(format!("{} = ", entry.roll.breakdown), text_style),
+        Span::styled(
+            entry.roll.total.to_string(),
+            text_style.add_modifier(Modifier::BOLD),
+        ),
+    ];
+    match entry.roll.natural_d20() {
+        Some(20) => spans.push(Span::styled(
+            "  NAT 20",
+            Style::default()
+                .fg(app.theme.positive)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Some(1) => spans.push(Span::styled(
+            "  NAT 1",
+            Style::default()
+                .fg(app.theme.negative)
+                .add_modifier(Modifier::BOLD),
+        )),
+        _ => {}
+    }
+    Line::from(spans)
+}
+
+fn describe_hp_change(change: &HpChange) -> String {
+    match change.kind {
+        HpChangeKind::Damage if change.temp_before > change.temp_after => format!(
+            "Took {} damage ({} absorbed by temp HP): {} -> {} HP",
+            change.amount,
+            change.temp_before - change.temp_after,
+            change.hp_before,
+            change.hp_after
+        ),
+        HpChangeKind::Damage => format!(
+            "Took {} damage: {} -> {} HP",
+            change.amount, change.hp_before, change.hp_after
+        ),
+        HpChangeKind::Healing => format!(
+            "Healed {}: {} -> {} HP",
+            change.amount, change.hp_before, change.hp_after
+        ),
+        HpChangeKind::TemporaryHp if change.temp_after == change.temp_before => format!(
+            "Kept {} temp HP (temp HP don't stack, {} is not higher)",
+            change.temp_after, change.amount
+        ),
+        HpChangeKind::TemporaryHp => format!("Temp HP set to {}", change.temp_after),
+    }
+}
+
+pub fn draw_title(frame: &mut Frame, theme: &Theme) -> Rc<[Rect]> {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -336,7 +786,7 @@ pub fn draw_title(frame: &mut Frame) -> Rc<[Rect]> {
     // Create paragraph for base app
     let title = Paragraph::new(Text::styled(
         "D&D Character Sheet",
-        Style::default().fg(Color::Green),
+        Style::default().fg(theme.primary),
     ))
     .block(title_block.clone());
 
@@ -355,8 +805,56 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
     let current_navigation_text = vec![
         // The first half of the text
         match app.current_screen {
-            CurrentScreen::Main => Span::styled("View Mode", Style::default().fg(Color::Green)),
-            CurrentScreen::Exiting => Span::styled("Exiting", Style::default().fg(Color::LightRed)),
+            CurrentScreen::Main if app.read_only => Span::styled(
+                "View Mode (read-only)",
+                Style::default().fg(app.theme.primary),
+            ),
+            CurrentScreen::Main if app.dirty => Span::styled(
+                "View Mode [modified]",
+                Style::default().fg(app.theme.notice),
+            ),
+            CurrentScreen::Main => {
+                Span::styled("View Mode", Style::default().fg(app.theme.primary))
+            }
+            CurrentScreen::Editing => {
+                Span::styled("Edit Mode", Style::default().fg(app.theme.notice))
+            }
+            CurrentScreen::EditingAbilities => Span::styled(
+                "Edit Mode (abilities)",
+                Style::default().fg(app.theme.notice),
+            ),
+            CurrentScreen::EditingProficiencies => Span::styled(
+                "Edit Mode (proficiencies)",
+                Style::default().fg(app.theme.notice),
+            ),
+            CurrentScreen::Rolling => {
+                Span::styled("Roll Mode", Style::default().fg(app.theme.notice))
+            }
+            CurrentScreen::Resting => {
+                Span::styled("Resting", Style::default().fg(app.theme.secondary))
+            }
+            CurrentScreen::Exiting => Span::styled("Exiting", Style::default().fg(app.theme.error)),
+            CurrentScreen::LoadFailed => {
+                Span::styled("Load Error", Style::default().fg(app.theme.error))
+            }
+            CurrentScreen::Backups => {
+                Span::styled("Backups", Style::default().fg(app.theme.secondary))
+            }
+            CurrentScreen::RollStats => {
+                Span::styled("Roll Statistics", Style::default().fg(app.theme.secondary))
+            }
+            CurrentScreen::Spells => {
+                Span::styled("Spells", Style::default().fg(app.theme.secondary))
+            }
+            CurrentScreen::Resources => {
+                Span::styled("Resources", Style::default().fg(app.theme.notice))
+            }
+            CurrentScreen::Inventory => {
+                Span::styled("Inventory", Style::default().fg(app.theme.secondary))
+            }
+            CurrentScreen::Wallet => {
+                Span::styled("Wallet", Style::default().fg(app.theme.secondary))
+            }
         }
         .to_owned(),
     ];
@@ -366,8 +864,134 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
 
     let current_keys_hint = {
         match app.current_screen {
-            CurrentScreen::Main => Span::styled("(q) to quit", Style::default().fg(Color::Red)),
-            CurrentScreen::Exiting => Span::styled("(q) to quit", Style::default().fg(Color::Red)),
+            CurrentScreen::Main => match (&app.status, &app.hp_prompt) {
+                (Some(status), _) => {
+                    Span::styled(status.clone(), Style::default().fg(app.theme.notice))
+                }
+                (None, Some(_)) => Span::styled(
+                    "(Enter) to confirm / (Esc) to cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+                (None, None) => Span::styled(
+                    "(e) edit / (a) abilities / (p) profs / (r) roll / (v) adv. / (c) spells / (u) uses / (i) items / (w) wallet / (^s) save",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::Editing => match (&app.status, &app.edit_input) {
+                (Some(status), _) => {
+                    Span::styled(status.clone(), Style::default().fg(app.theme.notice))
+                }
+                (None, Some(_)) => Span::styled(
+                    "(Enter) to confirm / (Esc) to cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+                (None, None) => Span::styled(
+                    "(arrows) select / (Enter) edit / (Esc) done",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::EditingAbilities => match (&app.status, &app.edit_input) {
+                (Some(status), _) => {
+                    Span::styled(status.clone(), Style::default().fg(app.theme.notice))
+                }
+                (None, Some(_)) => Span::styled(
+                    "(Enter) to confirm / (Esc) to cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+                (None, None) => Span::styled(
+                    "(arrows) select / (+/-) adjust / (Enter) type / (Esc) done",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::EditingProficiencies => match &app.status {
+                Some(status) => Span::styled(status.clone(), Style::default().fg(app.theme.notice)),
+                None => Span::styled(
+                    "(arrows) select / (Space) toggle / (Esc) done",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::Rolling => match &app.status {
+                Some(status) => Span::styled(status.clone(), Style::default().fg(app.theme.notice)),
+                None => Span::styled(
+                    "(arrows) select / (Enter) roll / (a) adv. / (d) disadv. / (v) next adv. / (i) init. / (s) stats / (Esc) done",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::Resting => match &app.rest {
+                Some(rest) if rest.kind == RestKind::Short => Span::styled(
+                    "(Space) spend a hit die / (Backspace) undo / (Enter) finish / (Esc) cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+                _ => Span::styled(
+                    "(Enter) finish the rest / (Esc) cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::Exiting => Span::styled(
+                "(s) save / (d) discard / (c) cancel",
+                Style::default().fg(app.theme.negative),
+            ),
+            CurrentScreen::Backups => match &app.status {
+                Some(status) => Span::styled(status.clone(), Style::default().fg(app.theme.notice)),
+                None => Span::styled(
+                    "(Enter) to restore / (^s) save / (Esc) to go back",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::RollStats => Span::styled(
+                "(arrows) select a session / (Esc) to go back",
+                Style::default().fg(app.theme.negative),
+            ),
+            CurrentScreen::Spells => match &app.status {
+                Some(status) => Span::styled(status.clone(), Style::default().fg(app.theme.notice)),
+                None => Span::styled(
+                    "(arrows) select / (Enter) cast / (p) prepare / (u) regain / (+/-) slots / (a) ability / (Esc) back",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::Resources => match &app.status {
+                Some(status) => Span::styled(status.clone(), Style::default().fg(app.theme.notice)),
+                None => Span::styled(
+                    "(arrows) select / (Enter/-) spend / (+) restore / (f) refill / (Esc) done",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::Inventory => match (&app.status, &app.item_prompt) {
+                (Some(status), _) => {
+                    Span::styled(status.clone(), Style::default().fg(app.theme.notice))
+                }
+                (None, Some(_)) => Span::styled(
+                    "(Enter) to add / (Esc) to cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+                (None, None) => Span::styled(
+                    "(a) add / (+/-) qty / (x) remove / (m) move / (e) equip / (t) attune / (c) container / (v) variant / (Esc) back",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::Wallet => match (&app.status, &app.money_prompt) {
+                (Some(status), _) => {
+                    Span::styled(status.clone(), Style::default().fg(app.theme.notice))
+                }
+                (None, Some(_)) => Span::styled(
+                    "(Enter) to confirm / (Esc) to cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+                (None, None) => Span::styled(
+                    "(p) pay / (r) receive / (c) consolidate / (arrows) scroll the ledger / (Esc) back",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
+            CurrentScreen::LoadFailed => match app.path_prompt {
+                Some(_) => Span::styled(
+                    "(Enter) to confirm / (Esc) to cancel",
+                    Style::default().fg(app.theme.negative),
+                ),
+                None => Span::styled(
+                    "(o) open another file / (n) new sheet / (q) to quit",
+                    Style::default().fg(app.theme.negative),
+                ),
+            },
         }
     };
 
@@ -376,7 +1000,7 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
 
     let footer_chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
         .split(area);
 
     frame.render_widget(mode_footer, footer_chunks[0]);
@@ -385,13 +1009,13 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
     if let CurrentScreen::Exiting = app.current_screen {
         frame.render_widget(Clear, frame.area()); //this clears the entire screen and anything already drawn
         let popup_block = Block::default()
-            .title("Y/N")
+            .title("Unsaved changes")
             .borders(Borders::NONE)
-            .style(Style::default().bg(Color::DarkGray));
+            .style(Style::default().bg(app.theme.popup_bg));
 
         let exit_text = Text::styled(
-            "Would you like to output the buffer as json? (y/n)",
-            Style::default().fg(Color::Red),
+            "Save your changes before quitting? (s)ave / (d)iscard / (c)ancel",
+            Style::default().fg(app.theme.negative),
         );
         // the `trim: false` will stop the text from being cut off when over the edge of the block
         let exit_paragraph = Paragraph::new(exit_text)
@@ -403,27 +1027,785 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
     }
 }
 
+fn draw_load_error(frame: &mut Frame, area: Rect, app: &App) {
+    let error_blk = Block::default()
+        .borders(Borders::ALL)
+        .title("Could not open character sheet")
+        .style(Style::default().fg(app.theme.error));
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw("File: "),
+            Span::styled(
+                app.json_file_name.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    if let Some(err) = &app.load_error {
+        lines.push(Line::from(err.to_string()));
+    }
+    lines.push(Line::from(""));
+
+    match &app.path_prompt {
+        Some(prompt) => {
+            let label = match prompt.action {
+                PathAction::Open => "Open file: ",
+                PathAction::New => "New sheet at: ",
+            };
+            let mut spans = vec![Span::raw(label)];
+            spans.extend(input_spans(
+                &prompt.input,
+                Style::default().fg(app.theme.text),
+            ));
+            lines.push(Line::from(spans));
+        }
+        None => {
+            lines.push(Line::from(
+                "Press (o) to open another file, (n) to start a new sheet or (q) to quit.",
+            ));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(error_blk)
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_backups(frame: &mut Frame, area: Rect, app: &App) {
+    let backups_blk = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Backups of {}", app.json_file_name))
+        .style(Style::default().fg(app.theme.secondary));
+
+    if app.backups.is_empty() {
+        let paragraph =
+            Paragraph::new("No backups yet. One is kept every time the sheet is saved.")
+                .block(backups_blk);
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = app
+        .backups
+        .iter()
+        .enumerate()
+        .map(|(i, backup)| {
+            let saved = backup
+                .saved
+                .map(storage::format_timestamp)
+                .unwrap_or_else(|| "unknown time".into());
+            let item = ListItem::new(format!(
+                "{:>2}  {}  {}",
+                backup.index,
+                saved,
+                backup.path.display()
+            ));
+            if i == app.selected_backup {
+                item.style(Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                item
+            }
+        })
+        .collect();
+
+    frame.render_widget(List::new(items).block(backups_blk), area);
+}
+
+/// Sessions on the left, the selected one's numbers and d20 distribution on
+/// the right. The first entry adds every session together.
+fn draw_roll_stats(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(40), Constraint::Min(0)])
+        .split(area);
+
+    let mut all = RollStats::default();
+    for session in &app.roll_sessions {
+        all.combine(&session.stats);
+    }
+
+    let titles = std::iter::once(("All sessions".to_string(), all.rolls)).chain(
+        app.roll_sessions.iter().map(|session| {
+            let started = UNIX_EPOCH + Duration::from_secs(session.session);
+            (storage::format_timestamp(started), session.stats.rolls)
+        }),
+    );
+    let items: Vec<ListItem> = titles
+        .enumerate()
+        .map(|(i, (title, rolls))| {
+            let item = ListItem::new(format!("{title:<24} {rolls:>5} rolls"));
+            if i == app.selected_roll_session {
+                item.style(Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                item
+            }
+        })
+        .collect();
+    let sessions_blk = Block::default()
+        .borders(Borders::ALL)
+        .title("Sessions")
+        .style(Style::default().fg(app.theme.secondary));
+    frame.render_widget(List::new(items).block(sessions_blk), chunks[0]);
+
+    let stats = match app.selected_roll_session {
+        0 => all,
+        i => app.roll_sessions[i - 1].stats,
+    };
+    let stats_blk = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Rolls of {}", app.json_file_name))
+        .style(Style::default().fg(app.theme.primary));
+    let inner = stats_blk.inner(chunks[1]);
+    frame.render_widget(stats_blk, chunks[1]);
+
+    if stats.rolls == 0 {
+        frame.render_widget(
+            Paragraph::new("No rolls yet. Everything rolled from the sheet is kept here."),
+            inner,
+        );
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)])
+        .split(inner);
+
+    let average = stats
+        .average_d20()
+        .map_or("--".to_string(), |average| format!("{average:.2}"));
+    let summary = vec![
+        Line::from(format!(
+            "Rolls: {}   d20 rolls: {}   Average d20: {average} (a fair die averages 10.50)",
+            stats.rolls,
+            stats.d20_rolls()
+        )),
+        Line::from(vec![
+            Span::styled(
+                format!("Natural 20s: {}", stats.natural_20s()),
+                Style::default().fg(app.theme.positive),
+            ),
+            Span::raw("   "),
+            Span::styled(
+                format!("Natural 1s: {}", stats.natural_1s()),
+                Style::default().fg(app.theme.negative),
+            ),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(summary), rows[0]);
+
+    let bars: Vec<Bar> = (1..=20)
+        .zip(stats.distribution)
+        .map(|(face, count)| {
+            let color = match face {
+                20 => app.theme.positive,
+                1 => app.theme.negative,
+                _ => app.theme.text,
+            };
+            Bar::default()
+                .value(u64::from(count))
+                .label(Line::from(face.to_string()))
+                .style(Style::default().fg(color))
+        })
+        .collect();
+    let chart = BarChart::default()
+        .block(Block::default().title("d20 faces"))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(3)
+        .bar_gap(1);
+    frame.render_widget(chart, rows[1]);
+}
+
+/// Casting stats and spell slots on top, the spell list below.
+fn draw_spells(frame: &mut Frame, area: Rect, app: &App) {
+    let spells_blk = Block::default()
+        .borders(Borders::ALL)
+        .title("Spellcasting")
+        .style(Style::default().fg(app.theme.primary));
+
+    let Some(spellcasting) = &app.char_sheet.spellcasting else {
+        let paragraph = Paragraph::new(
+            "This character doesn't cast spells. (a) to pick a casting ability and start.",
+        )
+        .block(spells_blk);
+        frame.render_widget(paragraph, area);
+        return;
+    };
+
+    let inner = spells_blk.inner(area);
+    frame.render_widget(spells_blk, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(inner);
+
+    frame.render_widget(
+        Paragraph::new(spell_header_lines(spellcasting, app)),
+        chunks[0],
+    );
+
+    let list_blk = Block::default().borders(Borders::TOP).title("Spells");
+    if spellcasting.spells.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No spells yet. Add them to the sheet under spellcasting.spells.")
+                .block(list_blk),
+            chunks[1],
+        );
+        return;
+    }
+
+    let mut items = vec![ListItem::new(format!(
+        "  {:<28} {:<8} {:<14} {:<10} {}",
+        "Name", "Level", "School", "Comp.", "Conc. / Ritual"
+    ))
+    .style(Style::default().add_modifier(Modifier::BOLD))];
+    items.extend(spellcasting.spells.iter().enumerate().map(|(i, spell)| {
+        let castable = spellcasting.castable(spell);
+        let marker = match (spell.level, castable) {
+            (0, _) => " ",
+            (_, true) => "●",
+            (_, false) => "○",
+        };
+        let flags = [(spell.concentration, "C"), (spell.ritual, "R")]
+            .into_iter()
+            .filter_map(|(has, flag)| has.then_some(flag))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let item = ListItem::new(format!(
+            "{marker} {:<28} {:<8} {:<14} {:<10} {flags}",
+            spell.name,
+            spells::level_name(spell.level),
+            spell.school.name(),
+            spell.components.short()
+        ));
+        let mut style = Style::default().fg(app.theme.text);
+        if !castable {
+            style = style.add_modifier(Modifier::DIM);
+        }
+        if i == app.selected_spell {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        item.style(style)
+    }));
+    frame.render_widget(List::new(items).block(list_blk), chunks[1]);
+}
+
+fn draw_inventory(frame: &mut Frame, area: Rect, app: &App) {
+    let inventory_blk = Block::default()
+        .borders(Borders::ALL)
+        .title("Inventory")
+        .style(Style::default().fg(app.theme.primary));
+    let inner = inventory_blk.inner(area);
+    frame.render_widget(inventory_blk, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(0),
+            Constraint::Length(2),
+        ])
+        .split(inner);
+
+    let sheet = &app.char_sheet;
+    let inventory = &sheet.inventory;
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let encumbrance = sheet.encumbrance();
+    let load_style = match encumbrance {
+        Encumbrance::Unencumbered => Style::default().fg(app.theme.positive),
+        Encumbrance::Encumbered | Encumbrance::HeavilyEncumbered => {
+            Style::default().fg(app.theme.notice)
+        }
+        Encumbrance::OverCapacity => Style::default().fg(app.theme.error),
+    };
+    let header = vec![
+        Line::from(vec![
+            Span::raw("Carrying "),
+            Span::styled(
+                format!(
+                    "{} / {} lb",
+                    inventory::format_weight(inventory.total_weight()),
+                    inventory::format_weight(sheet.carrying_capacity())
+                ),
+                bold,
+            ),
+            Span::raw("  "),
+            Span::styled(encumbrance.label(), load_style),
+            Span::raw("  Speed: "),
+            speed_span(sheet, &app.theme),
+        ]),
+        Line::from(vec![
+            Span::raw(format!(
+                "Attuned: {}/{}  Variant encumbrance: ",
+                inventory.attuned(),
+                rules::MAX_ATTUNED_ITEMS
+            )),
+            Span::styled(
+                if inventory.variant_encumbrance {
+                    "on"
+                } else {
+                    "off"
+                },
+                bold,
+            ),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(header), chunks[0]);
+
+    let list_blk = Block::default().borders(Borders::TOP).title("Items");
+    let rows = inventory.rows();
+    if rows.is_empty() {
+        frame.render_widget(
+            Paragraph::new("Nothing carried yet. (a) to add an item.").block(list_blk),
+            chunks[1],
+        );
+    } else {
+        let mut items = vec![ListItem::new(format!(
+            "    {:<34} {:>5} {:>9} {:>8}  {}",
+            "Name", "Qty", "Weight", "Cost", "Holds"
+        ))
+        .style(bold)];
+        items.extend(rows.iter().enumerate().map(|(i, (path, item))| {
+            let flags = format!(
+                "{}{}",
+                if item.equipped { "E" } else { " " },
+                if item.attuned { "A" } else { " " }
+            );
+            let name = format!("{}{}", "  ".repeat(path.len() - 1), item.name);
+            let holds = match &item.container {
+                Some(container) => {
+                    let load = match container.capacity {
+                        Some(capacity) => format!(
+                            "{} / {} lb",
+                            inventory::format_weight(container.load()),
+                            inventory::format_weight(capacity)
+                        ),
+                        None => format!("{} lb", inventory::format_weight(container.load())),
+                    };
+                    match container.contents_weight {
+                        ContentsWeight::Counted => load,
+                        ContentsWeight::Ignored => format!("{load}, weightless"),
+                    }
+                }
+                None => String::new(),
+            };
+            let line = ListItem::new(format!(
+                "{flags}  {name:<34} {:>5} {:>9} {:>8}  {holds}",
+                item.quantity,
+                format!("{} lb", inventory::format_weight(item.total_weight())),
+                wallet::format_amount(u64::from(item.cost_cp))
+            ));
+            let style = Style::default().fg(app.theme.text);
+            if i == app.selected_item {
+                line.style(style.add_modifier(Modifier::REVERSED))
+            } else {
+                line.style(style)
+            }
+        }));
+        frame.render_widget(List::new(items).block(list_blk), chunks[1]);
+    }
+
+    let footer = match &app.item_prompt {
+        Some(input) => {
+            let mut spans = vec![Span::raw("Add (name; quantity; weight; cost): ")];
+            spans.extend(input_spans(input, Style::default().fg(app.theme.text)));
+            Line::from(spans)
+        }
+        None => Line::styled(
+            rows.get(app.selected_item)
+                .map(|(_, item)| item.description.clone())
+                .unwrap_or_default(),
+            Style::default().fg(app.theme.secondary),
+        ),
+    };
+    frame.render_widget(
+        Paragraph::new(footer)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::TOP)),
+        chunks[2],
+    );
+}
+
+fn draw_wallet(frame: &mut Frame, area: Rect, app: &App) {
+    let wallet_blk = Block::default()
+        .borders(Borders::ALL)
+        .title("Wallet")
+        .style(Style::default().fg(app.theme.primary));
+    let inner = wallet_blk.inner(area);
+    frame.render_widget(wallet_blk, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(0),
+            Constraint::Length(2),
+        ])
+        .split(inner);
+
+    let wallet = &app.char_sheet.wallet;
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut coins = Vec::new();
+    for coin in Coin::ALL {
+        coins.push(Span::styled(wallet.coins(coin).to_string(), bold));
+        coins.push(Span::raw(format!(" {}   ", coin.abbreviation())));
+    }
+    let header = vec![
+        Line::from(coins),
+        Line::from(vec![
+            Span::raw("Worth "),
+            Span::styled(wallet::format_amount(wallet.total_cp()), bold),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(header), chunks[0]);
+
+    let title = if app.ledger_scroll > 0 {
+        format!("Ledger ({} newer above)", app.ledger_scroll)
+    } else {
+        "Ledger".to_string()
+    };
+    let ledger_blk = Block::default().borders(Borders::TOP).title(title);
+    if wallet.ledger.is_empty() {
+        frame.render_widget(
+            Paragraph::new("Nothing paid or received yet.").block(ledger_blk),
+            chunks[1],
+        );
+    } else {
+        let items: Vec<ListItem> = wallet
+            .ledger
+            .iter()
+            .rev()
+            .skip(app.ledger_scroll)
+            .map(|transaction| {
+                let time = UNIX_EPOCH + Duration::from_secs(transaction.time);
+                let (sign, colour) = if transaction.amount_cp < 0 {
+                    ("-", app.theme.negative)
+                } else {
+                    ("+", app.theme.positive)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<25}", storage::format_timestamp(time)),
+                        Style::default().fg(app.theme.secondary),
+                    ),
+                    Span::styled(
+                        format!(
+                            "{sign}{:<20}",
+                            wallet::format_amount(transaction.amount_cp.unsigned_abs())
+                        ),
+                        Style::default().fg(colour),
+                    ),
+                    Span::styled(
+                        transaction.note.clone(),
+                        Style::default().fg(app.theme.text),
+                    ),
+                ]))
+            })
+            .collect();
+        frame.render_widget(List::new(items).block(ledger_blk), chunks[1]);
+    }
+
+    let footer = match &app.money_prompt {
+        Some(prompt) => {
+            let label = match prompt.kind {
+                MoneyKind::Pay => "Pay (amount; note): ",
+                MoneyKind::Receive => "Receive (amount; note): ",
+            };
+            let mut spans = vec![Span::raw(label)];
+            spans.extend(input_spans(
+                &prompt.input,
+                Style::default().fg(app.theme.text),
+            ));
+            Line::from(spans)
+        }
+        None => Line::styled(
+            "Amounts look like 5 gp 3 sp. Paying makes change from the coins carried.",
+            Style::default().fg(app.theme.secondary),
+        ),
+    };
+    frame.render_widget(
+        Paragraph::new(footer).block(Block::default().borders(Borders::TOP)),
+        chunks[2],
+    );
+}
+
+fn spell_header_lines(spellcasting: &Spellcasting, app: &App) -> Vec<Line<'static>> {
+    let sheet = &app.char_sheet;
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let stats = Line::from(vec![
+        Span::raw("Casting ability: "),
+        Span::styled(
+            format!(
+                "{} ({:+})",
+                spellcasting.ability.abbreviation(),
+                sheet.statistics.modifier(spellcasting.ability)
+            ),
+            bold,
+        ),
+        Span::raw("   (P) Casts: "),
+        Span::styled(format!("{} spells", spellcasting.preparation.name()), bold),
+        Span::raw("   Spell save DC: "),
+        Span::styled(sheet.spell_save_dc().unwrap_or_default().to_string(), bold),
+        Span::raw("   Spell attack: "),
+        Span::styled(
+            format!("{:+}", sheet.spell_attack_bonus().unwrap_or_default()),
+            bold,
+        ),
+    ]);
+
+    // Every pool with slots, plus the one being cast from so it can be given
+    // some with +.
+    let mut slots = vec![Span::raw("Slots: ")];
+    let sources = (1..=rules::MAX_SPELL_LEVEL)
+        .map(SlotSource::Level)
+        .chain([SlotSource::Pact]);
+    for source in sources {
+        let pool = spellcasting.slots_of(source);
+        let selected = source == app.cast_slot;
+        if pool.max == 0 && !selected {
+            continue;
+        }
+        let name = match (source, spellcasting.slot_level(source)) {
+            (SlotSource::Level(level), _) => spells::level_name(level),
+            (SlotSource::Pact, Some(level)) => format!("Pact ({})", spells::level_name(level)),
+            (SlotSource::Pact, None) => "Pact".into(),
+        };
+        let label = Span::styled(
+            name,
+            if selected {
+                bold.add_modifier(Modifier::REVERSED)
+            } else {
+                bold
+            },
+        );
+        let pips = match pool.max {
+            0 => "--".to_string(),
+            _ => {
+                "●".repeat(pool.available().into())
+                    + &"○".repeat(pool.expended.min(pool.max).into())
+            }
+        };
+        slots.push(label);
+        slots.push(Span::styled(
+            format!(" {pips}   "),
+            Style::default().fg(app.theme.positive),
+        ));
+    }
+
+    let slot = match (app.cast_slot, spellcasting.slot_level(app.cast_slot)) {
+        (SlotSource::Level(level), _) => format!("a {} level slot", spells::level_name(level)),
+        (SlotSource::Pact, Some(level)) => {
+            format!("a pact slot ({} level)", spells::level_name(level))
+        }
+        (SlotSource::Pact, None) => String::new(),
+    };
+    let casting = match spellcasting.spells.get(app.selected_spell) {
+        _ if slot.is_empty() => "No pact magic. (+) to add a pact slot".into(),
+        Some(spell) if spell.level == 0 => format!("{} is a cantrip and needs no slot", spell.name),
+        Some(spell)
+            if spellcasting
+                .slot_level(app.cast_slot)
+                .is_some_and(|level| level > spell.level) =>
+        {
+            format!("Casting {} with {slot} (upcast)", spell.name)
+        }
+        Some(spell) => format!("Casting {} with {slot}", spell.name),
+        None => String::new(),
+    };
+    let casting = if app.cast_slot == SlotSource::Pact && !slot.is_empty() {
+        format!("{casting}   (+/-) pact slots / (</>) pact slot level")
+    } else {
+        casting
+    };
+
+    vec![
+        stats,
+        Line::from(slots),
+        Line::from(""),
+        Line::styled(casting, Style::default().fg(app.theme.text)),
+    ]
+}
+
 pub fn ui(frame: &mut Frame, app: &mut App, view_state: &mut ViewState) {
-    let chunks = draw_title(frame);
+    let chunks = draw_title(frame, &app.theme);
+
+    // Screens that replace the whole sheet between the title and the footer.
+    let body = Rect::new(
+        chunks[1].x,
+        chunks[1].y,
+        chunks[1].width,
+        chunks[chunks.len() - 1].y - chunks[1].y,
+    );
+    match app.current_screen {
+        CurrentScreen::LoadFailed => {
+            draw_load_error(frame, body, app);
+            draw_footer(frame, chunks[chunks.len() - 1], app);
+            return;
+        }
+        CurrentScreen::Backups => {
+            draw_backups(frame, body, app);
+            draw_footer(frame, chunks[chunks.len() - 1], app);
+            return;
+        }
+        CurrentScreen::RollStats => {
+            draw_roll_stats(frame, body, app);
+            draw_footer(frame, chunks[chunks.len() - 1], app);
+            return;
+        }
+        CurrentScreen::Spells => {
+            draw_spells(frame, body, app);
+            draw_footer(frame, chunks[chunks.len() - 1], app);
+            return;
+        }
+        CurrentScreen::Inventory => {
+            draw_inventory(frame, body, app);
+            draw_footer(frame, chunks[chunks.len() - 1], app);
+            return;
+        }
+        CurrentScreen::Wallet => {
+            draw_wallet(frame, body, app);
+            draw_footer(frame, chunks[chunks.len() - 1], app);
+            return;
+        }
+        CurrentScreen::Main
+        | CurrentScreen::Editing
+        | CurrentScreen::EditingAbilities
+        | CurrentScreen::EditingProficiencies
+        | CurrentScreen::Rolling
+        | CurrentScreen::Resources
+        | CurrentScreen::Resting
+        | CurrentScreen::Exiting => {}
+    }
 
     let info_chunk = chunks[1];
     let health_chunk = chunks[2];
     let stats_chunk = chunks[3];
-    let _prof_and_lang_chunk = chunks[4];
+    let roll_log_chunk = chunks[4];
     let footer_chunk = chunks[chunks.len() - 1];
 
-    draw_char_info(frame, info_chunk, app);
+    draw_char_info(frame, info_chunk, app, &mut view_state.info);
 
-    draw_abilities(frame, stats_chunk, app);
+    draw_abilities(frame, stats_chunk, app, &mut view_state.stats);
 
-    // Create a Rectangle to display player AC/HP/Temp HP/Initiative/Speed
-    // I am thinking of having the death saves/death fails
-    // be hidden until the player goes to 0 HP then have that pop up.
-    // I need to think through the use cases and ensure we support
-    // the char dying and being revived and being "down" but able to try death saves.
+    // Create a Rectangle to display player AC/HP/Temp HP/Initiative/Speed.
+    // Death saves take the place of the combat stats while the character is
+    // at 0 HP.
     draw_health(frame, health_chunk, app, &mut view_state.health);
 
+    // Resources share the bottom row with the roll log, on sheets that
+    // track any.
+    if app.char_sheet.resources.is_empty() {
+        view_state.resources.clear();
+        draw_roll_log(frame, roll_log_chunk, app, view_state);
+    } else {
+        let bottom_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(roll_log_chunk);
+        draw_resources(frame, bottom_chunks[0], app, view_state);
+        draw_roll_log(frame, bottom_chunks[1], app, view_state);
+    }
+
     draw_footer(frame, footer_chunk, app);
+
+    if let CurrentScreen::Resting = app.current_screen {
+        draw_rest(frame, app);
+    }
+}
+
+/// The rest dialog: what the rest will restore, shown over the sheet until
+/// the player finishes or cancels it.
+fn draw_rest(frame: &mut Frame, app: &App) {
+    let Some(rest) = &app.rest else {
+        return;
+    };
+    let sheet = &app.char_sheet;
+    let summary = rest.summary(sheet);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut lines = vec![Line::from(vec![
+        Span::raw("HP: "),
+        Span::styled(
+            format!("{} -> {}", summary.hp_before, summary.hp_after),
+            bold,
+        ),
+        Span::raw(format!(" of {}", sheet.health.maximum_hp)),
+    ])];
+    lines.push(Line::from(vec![
+        Span::raw(format!("Hit dice ({}): ", sheet.health.hit_dice_type)),
+        Span::styled(
+            format!("{} -> {}", summary.hit_dice_before, summary.hit_dice_after),
+            bold,
+        ),
+        Span::raw(format!(" of {}", sheet.health.total_hit_dice)),
+    ]));
+
+    match rest.kind {
+        RestKind::Short => {
+            let constitution = sheet.statistics.modifier(Ability::Constitution);
+            let rolls: Vec<String> = rest
+                .hit_die_rolls
+                .iter()
+                .zip(&summary.hit_die_healing)
+                .map(|(die, healed)| format!("{die}{constitution:+} = {healed}"))
+                .collect();
+            lines.push(Line::from(""));
+            if rolls.is_empty() {
+                lines.push(Line::from("No hit dice spent yet."));
+            } else {
+                lines.push(Line::from(format!("Rolled: {}", rolls.join(", "))));
+            }
+            if !rest.can_spend_hit_die(sheet) {
+                let message = match sheet.health.hit_die_sides() {
+                    Some(_) => "No hit dice left to spend.".into(),
+                    None => format!(
+                        "Hit dice can only be rolled for one die such as d8, not '{}'.",
+                        sheet.health.hit_dice_type
+                    ),
+                };
+                lines.push(Line::from(Span::styled(
+                    message,
+                    Style::default().fg(app.theme.notice),
+                )));
+            }
+        }
+        RestKind::Long => {
+            if summary.death_saves_reset {
+                lines.push(Line::from("Death saves are reset."));
+            }
+            if summary.spell_slots_regained > 0 {
+                lines.push(Line::from(format!(
+                    "{} spell slots regained.",
+                    summary.spell_slots_regained
+                )));
+            }
+        }
+    }
+    if summary.pact_slots_regained > 0 {
+        lines.push(Line::from(format!(
+            "{} pact slots regained.",
+            summary.pact_slots_regained
+        )));
+    }
+    for (name, regained) in &summary.resources_recharged {
+        lines.push(Line::from(format!("{regained} {name} regained.")));
+    }
+
+    let rest_blk = Block::default()
+        .borders(Borders::ALL)
+        .title(rest.kind.label())
+        .style(Style::default().fg(app.theme.secondary));
+    let area = centered_rect(50, 40, frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(rest_blk)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
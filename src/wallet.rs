@@ -0,0 +1,371 @@
+// Money. The wallet holds coins of each denomination and a ledger of every
+// amount paid or received, so the party can look back on who spent what.
+// Amounts are worked in copper pieces.
+
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::history;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coin {
+    Copper,
+    Silver,
+    Electrum,
+    Gold,
+    Platinum,
+}
+
+impl Coin {
+    /// Smallest first.
+    pub const ALL: [Coin; 5] = [
+        Coin::Copper,
+        Coin::Silver,
+        Coin::Electrum,
+        Coin::Gold,
+        Coin::Platinum,
+    ];
+
+    // What change and consolidated coins are given in. Nobody hands out
+    // electrum.
+    const CHANGE: [Coin; 4] = [Coin::Platinum, Coin::Gold, Coin::Silver, Coin::Copper];
+
+    pub fn value_cp(self) -> u64 {
+        match self {
+            Coin::Copper => 1,
+            Coin::Silver => 10,
+            Coin::Electrum => 50,
+            Coin::Gold => 100,
+            Coin::Platinum => 1000,
+        }
+    }
+
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            Coin::Copper => "cp",
+            Coin::Silver => "sp",
+            Coin::Electrum => "ep",
+            Coin::Gold => "gp",
+            Coin::Platinum => "pp",
+        }
+    }
+
+    pub fn parse(unit: &str) -> Option<Coin> {
+        Coin::ALL
+            .into_iter()
+            .find(|coin| coin.abbreviation().eq_ignore_ascii_case(unit))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Wallet {
+    #[serde(default)]
+    pub cp: u32,
+    #[serde(default)]
+    pub sp: u32,
+    #[serde(default)]
+    pub ep: u32,
+    #[serde(default)]
+    pub gp: u32,
+    #[serde(default)]
+    pub pp: u32,
+    /// Oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ledger: Vec<Transaction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Transaction {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    /// Copper pieces received, negative when paid out.
+    pub amount_cp: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+}
+
+/// The coins handed over for a payment and the change given back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Payment {
+    pub paid: Vec<(Coin, u32)>,
+    pub change: Vec<(Coin, u32)>,
+}
+
+impl Wallet {
+    pub fn is_empty(&self) -> bool {
+        self.total_cp() == 0 && self.ledger.is_empty()
+    }
+
+    pub fn coins(&self, coin: Coin) -> u32 {
+        match coin {
+            Coin::Copper => self.cp,
+            Coin::Silver => self.sp,
+            Coin::Electrum => self.ep,
+            Coin::Gold => self.gp,
+            Coin::Platinum => self.pp,
+        }
+    }
+
+    fn coins_mut(&mut self, coin: Coin) -> &mut u32 {
+        match coin {
+            Coin::Copper => &mut self.cp,
+            Coin::Silver => &mut self.sp,
+            Coin::Electrum => &mut self.ep,
+            Coin::Gold => &mut self.gp,
+            Coin::Platinum => &mut self.pp,
+        }
+    }
+
+    pub fn total_cp(&self) -> u64 {
+        Coin::ALL
+            .into_iter()
+            .map(|coin| u64::from(self.coins(coin)) * coin.value_cp())
+            .sum()
+    }
+
+    /// Puts `coins` in the wallet as they are and writes them in the ledger.
+    pub fn receive(&mut self, coins: &[(Coin, u32)], note: &str) {
+        for &(coin, count) in coins {
+            let held = self.coins_mut(coin);
+            *held = held.saturating_add(count);
+        }
+        self.record(total_cp(coins) as i64, note);
+    }
+
+    /// Pays `amount_cp`, using up the smallest coins first. When they don't
+    /// add up to the exact amount the smallest coin that covers the rest is
+    /// broken and the change comes back in gold, silver and copper.
+    pub fn pay(&mut self, amount_cp: u64, note: &str) -> Result<Payment, String> {
+        if amount_cp == 0 {
+            return Err("Enter an amount such as 5 gp".into());
+        }
+        let total = self.total_cp();
+        if amount_cp > total {
+            return Err(format!(
+                "That costs {} but the wallet only holds {}",
+                format_amount(amount_cp),
+                format_amount(total)
+            ));
+        }
+
+        let mut paid = Vec::new();
+        let mut remaining = amount_cp;
+        for coin in Coin::ALL {
+            let count = (remaining / coin.value_cp()).min(u64::from(self.coins(coin))) as u32;
+            if count > 0 {
+                *self.coins_mut(coin) -= count;
+                remaining -= u64::from(count) * coin.value_cp();
+                paid.push((coin, count));
+            }
+        }
+
+        // Every coin left is now worth more than what is still owed, or the
+        // total would have come up short.
+        let mut change = Vec::new();
+        if remaining > 0
+            && let Some(coin) = Coin::ALL.into_iter().find(|coin| self.coins(*coin) > 0)
+        {
+            *self.coins_mut(coin) -= 1;
+            match paid.iter_mut().find(|(paid, _)| *paid == coin) {
+                Some((_, count)) => *count += 1,
+                None => paid.push((coin, 1)),
+            }
+            change = make_change(coin.value_cp() - remaining);
+            for &(coin, count) in &change {
+                let held = self.coins_mut(coin);
+                *held = held.saturating_add(count);
+            }
+        }
+
+        self.record(-(amount_cp as i64), note);
+        Ok(Payment { paid, change })
+    }
+
+    /// Trades the coins in for as few as possible of the same total value,
+    /// leaving electrum out. Returns whether any coins changed.
+    pub fn consolidate(&mut self) -> bool {
+        let coins = make_change(self.total_cp());
+        let changed = Coin::ALL.into_iter().any(|coin| {
+            let count = coins.iter().find(|(c, _)| *c == coin).map_or(0, |c| c.1);
+            count != self.coins(coin)
+        });
+        for coin in Coin::ALL {
+            *self.coins_mut(coin) = 0;
+        }
+        for (coin, count) in coins {
+            *self.coins_mut(coin) = count;
+        }
+        changed
+    }
+
+    fn record(&mut self, amount_cp: i64, note: &str) {
+        self.ledger.push(Transaction {
+            time: history::unix_time(SystemTime::now()),
+            amount_cp,
+            note: note.trim().to_string(),
+        });
+    }
+}
+
+/// `amount_cp` in the fewest platinum, gold, silver and copper coins,
+/// largest first.
+fn make_change(mut amount_cp: u64) -> Vec<(Coin, u32)> {
+    let mut coins = Vec::new();
+    for coin in Coin::CHANGE {
+        let count = amount_cp / coin.value_cp();
+        if count > 0 {
+            coins.push((coin, count.min(u64::from(u32::MAX)) as u32));
+            amount_cp -= count * coin.value_cp();
+        }
+    }
+    coins
+}
+
+pub fn total_cp(coins: &[(Coin, u32)]) -> u64 {
+    coins
+        .iter()
+        .map(|&(coin, count)| u64::from(count) * coin.value_cp())
+        .sum()
+}
+
+/// Reads an amount such as `5 gp 3 sp` or `12sp`. A plain number is taken
+/// as gold, the usual unit of prices.
+pub fn parse_amount(text: &str) -> Result<Vec<(Coin, u32)>, String> {
+    let error = || format!("'{}' is not an amount such as 5 gp 3 sp", text.trim());
+    let mut coins = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let count: u32 = rest[..digits].parse().map_err(|_| error())?;
+        rest = rest[digits..].trim_start();
+
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let coin = match &rest[..letters] {
+            "" if coins.is_empty() && rest.is_empty() => Coin::Gold,
+            unit => Coin::parse(unit).ok_or_else(error)?,
+        };
+        coins.push((coin, count));
+        rest = rest[letters..].trim_start_matches([' ', ',']);
+    }
+    if coins.is_empty() {
+        return Err(error());
+    }
+    Ok(coins)
+}
+
+/// Like `parse_amount`, as a single number of copper pieces.
+pub fn parse_cost(text: &str) -> Result<u32, String> {
+    let total = total_cp(&parse_amount(text)?);
+    u32::try_from(total).map_err(|_| format!("'{}' is too much", text.trim()))
+}
+
+/// 1505 -> `15 gp 5 cp`, `-` for nothing.
+pub fn format_amount(amount_cp: u64) -> String {
+    if amount_cp == 0 {
+        return "-".into();
+    }
+    let mut left = amount_cp;
+    let mut parts = Vec::new();
+    for coin in [Coin::Gold, Coin::Silver, Coin::Copper] {
+        let count = left / coin.value_cp();
+        if count > 0 {
+            parts.push(format!("{count} {}", coin.abbreviation()));
+            left -= count * coin.value_cp();
+        }
+    }
+    parts.join(" ")
+}
+
+/// `1 gp, 5 sp`
+pub fn format_coins(coins: &[(Coin, u32)]) -> String {
+    coins
+        .iter()
+        .map(|(coin, count)| format!("{count} {}", coin.abbreviation()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallet(cp: u32, sp: u32, ep: u32, gp: u32, pp: u32) -> Wallet {
+        Wallet {
+            cp,
+            sp,
+            ep,
+            gp,
+            pp,
+            ledger: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_amounts() {
+        assert_eq!(
+            parse_amount("5 gp 3 sp").unwrap(),
+            [(Coin::Gold, 5), (Coin::Silver, 3)]
+        );
+        assert_eq!(
+            parse_amount("2pp, 12CP").unwrap(),
+            [(Coin::Platinum, 2), (Coin::Copper, 12)]
+        );
+        assert_eq!(parse_amount("7").unwrap(), [(Coin::Gold, 7)]);
+        assert!(parse_amount("5 gp 3").is_err());
+        assert!(parse_amount("5 xp").is_err());
+        assert!(parse_amount("").is_err());
+        assert_eq!(parse_cost("1 sp 5 cp"), Ok(15));
+        assert_eq!(format_amount(1505), "15 gp 5 cp");
+        assert_eq!(format_amount(50), "5 sp");
+    }
+
+    #[test]
+    fn pays_with_small_coins_first() {
+        let mut wallet = wallet(5, 20, 0, 3, 0);
+        let payment = wallet.pay(125, "rope").unwrap();
+
+        assert_eq!(payment.paid, [(Coin::Copper, 5), (Coin::Silver, 12)]);
+        assert!(payment.change.is_empty());
+        assert_eq!((wallet.cp, wallet.sp, wallet.gp), (0, 8, 3));
+        assert_eq!(wallet.ledger[0].amount_cp, -125);
+        assert_eq!(wallet.ledger[0].note, "rope");
+    }
+
+    #[test]
+    fn breaks_a_coin_and_takes_change() {
+        let mut wallet = wallet(0, 0, 1, 0, 1);
+        let payment = wallet.pay(275, "healing potion").unwrap();
+
+        // The electrum goes first, then the platinum is broken.
+        assert_eq!(payment.paid, [(Coin::Electrum, 1), (Coin::Platinum, 1)]);
+        assert_eq!(
+            payment.change,
+            [(Coin::Gold, 7), (Coin::Silver, 7), (Coin::Copper, 5)]
+        );
+        assert_eq!(wallet.total_cp(), 1050 - 275);
+    }
+
+    #[test]
+    fn refuses_to_overspend() {
+        let mut wallet = wallet(0, 0, 0, 1, 0);
+        assert!(wallet.pay(101, "").is_err());
+        assert_eq!(wallet.gp, 1);
+        assert!(wallet.ledger.is_empty());
+    }
+
+    #[test]
+    fn consolidates_into_fewer_coins() {
+        let mut wallet = wallet(123, 45, 3, 8, 0);
+        assert!(wallet.consolidate());
+        assert_eq!(
+            (wallet.cp, wallet.sp, wallet.ep, wallet.gp, wallet.pp),
+            (3, 2, 0, 5, 1)
+        );
+        assert!(!wallet.consolidate());
+    }
+}
@@ -0,0 +1,91 @@
+// Sheets saved by older versions of the app, loaded through every migration
+// step. `fixtures/legacy_character_sheet.json` is the sample sheet as it was
+// shipped before sheets carried a schema version.
+
+use char_sheet::migrate::CURRENT_SCHEMA_VERSION;
+use char_sheet::sheet::{Ability, CharSheet, LifeState, Skill, SkillProficiency};
+use char_sheet::storage;
+
+fn legacy_sheet() -> CharSheet {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/legacy_character_sheet.json"
+    );
+    storage::load_sheet(path).expect("the legacy sample sheet loads")
+}
+
+#[test]
+fn loads_at_the_current_schema_version() {
+    let sheet = legacy_sheet();
+
+    assert_eq!(sheet.schema_version, CURRENT_SCHEMA_VERSION);
+    assert_eq!(sheet.information.character_name, "Cromwell Windscream");
+    assert_eq!(sheet.statistics.strength, 19);
+}
+
+#[test]
+fn hand_typed_stats_that_match_the_rules_are_derived() {
+    let sheet = legacy_sheet();
+
+    assert!(!sheet.proficiency_bonus().overridden);
+    assert_eq!(sheet.proficiency_bonus().value, 3);
+    assert!(!sheet.passive_perception().overridden);
+    assert_eq!(sheet.passive_perception().value, 11);
+    assert!(!sheet.initiative().overridden);
+    assert_eq!(sheet.initiative().value, 2);
+}
+
+#[test]
+fn saving_throw_flags_become_a_map() {
+    let sheet = legacy_sheet();
+
+    for ability in Ability::ALL {
+        let proficient = matches!(ability, Ability::Strength | Ability::Constitution);
+        assert_eq!(
+            sheet.saving_throws.is_proficient(ability),
+            proficient,
+            "{ability:?}"
+        );
+    }
+}
+
+#[test]
+fn skill_strings_become_proficiency_levels() {
+    let sheet = legacy_sheet();
+    let proficiency = |skill| sheet.skills.entries[&skill].proficiency;
+
+    assert_eq!(proficiency(Skill::Athletics), SkillProficiency::Expertise);
+    assert_eq!(
+        proficiency(Skill::AnimalHandling),
+        SkillProficiency::Proficient
+    );
+    assert_eq!(proficiency(Skill::Survival), SkillProficiency::Proficient);
+    assert_eq!(proficiency(Skill::Acrobatics), SkillProficiency::None);
+    // Stored as `slight_of_hand` in the old format.
+    assert_eq!(proficiency(Skill::SleightOfHand), SkillProficiency::None);
+    assert_eq!(sheet.skills.entries.len(), Skill::ALL.len());
+
+    // The hand-typed totals are dropped in favour of derived ones.
+    assert!(sheet
+        .skills
+        .entries
+        .values()
+        .all(|entry| entry.total_override.is_none()));
+    assert_eq!(
+        sheet
+            .skills
+            .total(Skill::Athletics, &sheet.statistics, 3)
+            .value,
+        10
+    );
+}
+
+#[test]
+fn death_save_strings_become_counters() {
+    let sheet = legacy_sheet();
+
+    assert!(!sheet.health.unconscious);
+    assert_eq!(sheet.health.death_saves.successes, 0);
+    assert_eq!(sheet.health.death_saves.failures, 0);
+    assert_eq!(sheet.health.life_state(), LifeState::Conscious);
+}
@@ -0,0 +1,247 @@
+// Rolls made through a seeded or scripted `DieRoller`, and the rest and
+// death save rules built on top of them.
+
+use char_sheet::dice::{self, DieRoller, Rng};
+use char_sheet::resource::{Recharge, Resource};
+use char_sheet::rest::{Rest, RestKind};
+use char_sheet::sheet::{Ability, CharSheet, DeathSaveOutcome, LifeState};
+use char_sheet::spells::{Components, School, SlotSource, Spell, Spellcasting};
+
+/// Hands out the given faces in order, whatever die is asked for.
+struct Script(std::vec::IntoIter<u32>);
+
+impl Script {
+    fn new(faces: Vec<u32>) -> Script {
+        Script(faces.into_iter())
+    }
+}
+
+impl DieRoller for Script {
+    fn roll(&mut self, _sides: u32) -> u32 {
+        self.0.next().expect("the script ran out of rolls")
+    }
+}
+
+fn d20s(roller: &mut impl DieRoller, count: usize) -> Vec<u32> {
+    (0..count).map(|_| roller.roll(20)).collect()
+}
+
+fn dying_sheet() -> CharSheet {
+    let mut sheet = CharSheet::blank();
+    sheet.health.maximum_hp = 10;
+    sheet.health.current_hp = 10;
+    sheet.health.take_damage(10);
+    assert_eq!(sheet.health.life_state(), LifeState::Dying);
+    sheet
+}
+
+fn death_saves(sheet: &mut CharSheet, roller: &mut impl DieRoller) -> Vec<DeathSaveOutcome> {
+    let mut outcomes = Vec::new();
+    while sheet.health.life_state() == LifeState::Dying {
+        outcomes.extend(sheet.health.roll_death_save(roller.roll(20)));
+    }
+    outcomes
+}
+
+#[test]
+fn a_seed_replays_the_same_rolls() {
+    let expressions = ["1d20+5", "4d6kh3", "1d20adv", "8d6/2", "2d8+1d4-1"];
+    let (mut first, mut second) = (Rng::seeded(7), Rng::seeded(7));
+
+    for expression in expressions {
+        let a = dice::roll_expression(expression, &mut first, None).unwrap();
+        let b = dice::roll_expression(expression, &mut second, None).unwrap();
+        assert_eq!(a.dice, b.dice, "{expression}");
+        assert_eq!(a.total, b.total, "{expression}");
+    }
+}
+
+#[test]
+fn different_seeds_roll_differently() {
+    assert_ne!(d20s(&mut Rng::seeded(1), 20), d20s(&mut Rng::seeded(2), 20));
+}
+
+// A bug report quoting `--seed 42` has to mean the same rolls in every
+// version, so the generator itself must not change.
+#[test]
+fn seeded_sequence_is_stable() {
+    assert_eq!(d20s(&mut Rng::seeded(42), 8), [14, 12, 19, 5, 11, 3, 6, 9]);
+}
+
+#[test]
+fn three_successes_stabilise() {
+    let mut sheet = dying_sheet();
+    let outcomes = death_saves(&mut sheet, &mut Script::new(vec![10, 4, 15, 19]));
+
+    assert_eq!(
+        outcomes,
+        [
+            DeathSaveOutcome::Success,
+            DeathSaveOutcome::Failure,
+            DeathSaveOutcome::Success,
+            DeathSaveOutcome::Stabilized,
+        ]
+    );
+    assert_eq!(sheet.health.life_state(), LifeState::Stable);
+    assert_eq!(sheet.health.current_hp, 0);
+}
+
+#[test]
+fn a_natural_one_counts_twice() {
+    let mut sheet = dying_sheet();
+    let outcomes = death_saves(&mut sheet, &mut Script::new(vec![9, 1]));
+
+    assert_eq!(
+        outcomes,
+        [DeathSaveOutcome::Failure, DeathSaveOutcome::Died]
+    );
+    assert_eq!(sheet.health.life_state(), LifeState::Dead);
+}
+
+#[test]
+fn a_natural_twenty_revives() {
+    let mut sheet = dying_sheet();
+    let outcomes = death_saves(&mut sheet, &mut Script::new(vec![2, 20]));
+
+    assert_eq!(
+        outcomes,
+        [DeathSaveOutcome::Failure, DeathSaveOutcome::Revived]
+    );
+    assert_eq!(sheet.health.life_state(), LifeState::Conscious);
+    assert_eq!(sheet.health.current_hp, 1);
+    assert_eq!(sheet.health.death_saves.failures, 0);
+}
+
+#[test]
+fn seeded_death_saves_end_the_same_way() {
+    let mut first = dying_sheet();
+    let mut second = dying_sheet();
+
+    let a = death_saves(&mut first, &mut Rng::seeded(99));
+    let b = death_saves(&mut second, &mut Rng::seeded(99));
+
+    assert_eq!(a, b);
+    assert_ne!(first.health.life_state(), LifeState::Dying);
+    assert_eq!(first.health.life_state(), second.health.life_state());
+}
+
+#[test]
+fn short_rest_spends_rolled_hit_dice() {
+    let mut sheet = CharSheet::blank();
+    sheet.statistics.constitution = 14;
+    sheet.health.maximum_hp = 40;
+    sheet.health.current_hp = 10;
+    sheet.health.total_hit_dice = 3;
+    sheet.health.current_hit_dice = 3;
+
+    let mut roller = Script::new(vec![3, 8]);
+    let sides = sheet.health.hit_die_sides().unwrap();
+    let mut rest = Rest::new(RestKind::Short);
+    while rest.hit_die_rolls.len() < 2 && rest.can_spend_hit_die(&sheet) {
+        rest.hit_die_rolls.push(roller.roll(sides));
+    }
+    let summary = rest.apply(&mut sheet);
+
+    // Each die adds the Constitution modifier of +2.
+    assert_eq!(summary.hit_die_healing, [5, 10]);
+    assert_eq!(sheet.health.current_hp, 25);
+    assert_eq!(sheet.health.current_hit_dice, 1);
+}
+
+#[test]
+fn short_rest_stops_when_hit_dice_run_out() {
+    let mut sheet = CharSheet::blank();
+    sheet.health.maximum_hp = 20;
+    sheet.health.current_hp = 5;
+
+    let mut roller = Rng::seeded(3);
+    let mut rest = Rest::new(RestKind::Short);
+    while rest.can_spend_hit_die(&sheet) {
+        rest.hit_die_rolls.push(roller.roll(8));
+    }
+
+    assert_eq!(rest.hit_die_rolls.len(), 1);
+    rest.apply(&mut sheet);
+    assert_eq!(sheet.health.current_hit_dice, 0);
+    assert!(sheet.health.current_hp > 5);
+}
+
+#[test]
+fn long_rest_caps_hit_dice_at_the_total() {
+    let mut sheet = CharSheet::blank();
+    sheet.health.maximum_hp = 20;
+    sheet.health.current_hp = 20;
+    sheet.health.total_hit_dice = u8::MAX;
+    sheet.health.current_hit_dice = u8::MAX - 1;
+
+    let summary = Rest::new(RestKind::Long).apply(&mut sheet);
+    assert_eq!(summary.hit_dice_after, u8::MAX);
+    assert_eq!(sheet.health.current_hit_dice, u8::MAX);
+}
+
+#[test]
+fn short_rest_only_brings_back_pact_slots() {
+    let mut sheet = CharSheet::blank();
+    sheet.health.maximum_hp = 20;
+    sheet.health.current_hp = 20;
+    let mut spellcasting = Spellcasting::new(Ability::Charisma);
+    spellcasting.set_max_slots(1, 2);
+    spellcasting.set_max_pact_slots(1);
+    spellcasting.spells.push(Spell {
+        name: "Hex".into(),
+        level: 1,
+        school: School::Enchantment,
+        components: Components {
+            verbal: true,
+            somatic: true,
+            material: Some("the petrified eye of a newt".into()),
+        },
+        concentration: true,
+        ritual: false,
+        prepared: true,
+    });
+    spellcasting.cast(0, SlotSource::Pact).unwrap();
+    spellcasting.cast(0, SlotSource::Level(1)).unwrap();
+    sheet.spellcasting = Some(spellcasting);
+
+    let summary = Rest::new(RestKind::Short).apply(&mut sheet);
+    assert_eq!(summary.pact_slots_regained, 1);
+    assert_eq!(summary.spell_slots_regained, 0);
+
+    let spellcasting = sheet.spellcasting.as_ref().unwrap();
+    assert_eq!(spellcasting.expended_pact_slots(), 0);
+    assert_eq!(spellcasting.slots(1).available(), 1);
+
+    // A long rest brings back the rest.
+    let summary = Rest::new(RestKind::Long).apply(&mut sheet);
+    assert_eq!(summary.spell_slots_regained, 1);
+    assert_eq!(sheet.spellcasting.unwrap().slots(1).available(), 2);
+}
+
+#[test]
+fn mixed_hit_dice_still_allow_a_short_rest() {
+    let mut sheet = CharSheet::blank();
+    sheet.health.maximum_hp = 20;
+    sheet.health.current_hp = 12;
+    sheet.health.hit_dice_type = "d8/d6".into();
+    let mut spellcasting = Spellcasting::new(Ability::Charisma);
+    spellcasting.set_max_pact_slots(2);
+    spellcasting.pact_magic.as_mut().unwrap().slots.expended = 2;
+    sheet.spellcasting = Some(spellcasting);
+    sheet.resources.push(Resource {
+        name: "Sorcery Points".into(),
+        max: 3,
+        current: 0,
+        recharge: Recharge::ShortRest,
+        max_formula: None,
+    });
+
+    let rest = Rest::new(RestKind::Short);
+    assert_eq!(rest.check(&sheet), Ok(()));
+    assert!(!rest.can_spend_hit_die(&sheet));
+
+    let summary = rest.apply(&mut sheet);
+    assert_eq!(summary.pact_slots_regained, 2);
+    assert_eq!(sheet.resources[0].current, 3);
+    assert_eq!(sheet.health.current_hp, 12);
+}